@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use amethyst::{
     assets::{AssetLoaderSystemData, Handle},
     core::Transform,
@@ -6,16 +8,22 @@ use amethyst::{
     renderer::{formats::mesh::ObjFormat, ImageFormat, Material, MaterialDefaults, Mesh, Texture},
 };
 
+use serde::{Deserialize, Serialize};
+
+use super::{BlockGrid, Cell};
+
 pub const BLOCK_SIZE_FROM_CENTER: f32 = 0.5; // Defined from mesh in cube.obj
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum BlockSurface {
     Grass,
     Dirt,
     Gravel,
     StoneRough,
     StoneSmooth,
-    Snow
+    Snow,
+    Water,
+    Bedrock
 }
 
 impl ToString for BlockSurface {
@@ -26,11 +34,66 @@ impl ToString for BlockSurface {
             Self::Gravel => {"Gravel".to_string()},
             Self::StoneRough => {"Rough Stone".to_string()},
             Self::StoneSmooth => {"Smooth Stone".to_string()},
-            Self::Snow => {"Snow".to_string()}
+            Self::Snow => {"Snow".to_string()},
+            Self::Water => {"Water".to_string()},
+            Self::Bedrock => {"Bedrock".to_string()}
         }
     }
 }
 
+impl BlockSurface {
+    pub const ALL: [BlockSurface; 8] = [
+        Self::Grass,
+        Self::Dirt,
+        Self::Gravel,
+        Self::StoneRough,
+        Self::StoneSmooth,
+        Self::Snow,
+        Self::Water,
+        Self::Bedrock,
+    ];
+
+    /// File in `assets/texture`
+    pub fn texture_file(&self) -> &'static str {
+        match self {
+            Self::Grass => "grass.png",
+            Self::Dirt => "dirt.png",
+            Self::Gravel => "gravel.png",
+            Self::StoneRough => "stone_rough.png",
+            Self::StoneSmooth => "stone_smooth.png",
+            Self::Snow => "snow.png",
+            Self::Water => "water.png",
+            Self::Bedrock => "bedrock.png",
+        }
+    }
+
+    /// Whether the player collides with the block or can move through it
+    pub fn is_solid(&self) -> bool {
+        match self {
+            Self::Water => false,
+            _ => true
+        }
+    }
+
+    /// Whether the player can destroy the block
+    pub fn is_breakable(&self) -> bool {
+        match self {
+            Self::Bedrock => false,
+            _ => true
+        }
+    }
+
+    /// Name used for the block in commands, like `rough_stone`
+    pub fn command_name(&self) -> String {
+        self.to_string().to_lowercase().replace(' ', "_")
+    }
+
+    pub fn from_command_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        Self::ALL.iter().copied().find(|surface| surface.command_name() == name)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Block {
     pub x: f32,
@@ -47,17 +110,28 @@ impl Block {
     pub fn as_array(&self) -> [f32; 3] {
         [self.x, self.y, self.z]
     }
+
+    pub fn cell(&self) -> Cell {
+        BlockGrid::cell_at(self.x, self.y, self.z)
+    }
 }
 
 impl Component for Block {
     type Storage = DenseVecStorage<Self>;
 }
 
-fn get_mat(world: &mut World, file_name: &str, mat_default: Material) -> Handle<Material> {
-    let texture = world.exec(|loader: AssetLoaderSystemData<'_, Texture>| {
+pub fn get_texture(world: &mut World, file_name: &str) -> Handle<Texture> {
+    world.exec(|loader: AssetLoaderSystemData<'_, Texture>| {
         loader.load(format!("texture/{file_name}"), ImageFormat::default(), ())
-    });
+    })
+}
 
+pub fn get_mat(world: &mut World, file_name: &str, mat_default: Material) -> Handle<Material> {
+    let texture = get_texture(world, file_name);
+    texture_mat(world, texture, mat_default)
+}
+
+fn texture_mat(world: &mut World, texture: Handle<Texture>, mat_default: Material) -> Handle<Material> {
     let mat_handle = world.exec(|loader: AssetLoaderSystemData<'_, Material>| {
         loader.load_from_data(
             Material {
@@ -71,39 +145,73 @@ fn get_mat(world: &mut World, file_name: &str, mat_default: Material) -> Handle<
     mat_handle
 }
 
+/// Mesh, materials and textures shared by all blocks
+pub struct BlockAssets {
+    pub mesh: Handle<Mesh>,
+    pub materials: HashMap<BlockSurface, Handle<Material>>,
+    pub textures: HashMap<BlockSurface, Handle<Texture>>, // Used for icons in the UI
+}
+
+impl BlockAssets {
+    pub fn material(&self, surface: BlockSurface) -> Handle<Material> {
+        self.materials[&surface].clone()
+    }
+
+    pub fn texture(&self, surface: BlockSurface) -> Handle<Texture> {
+        self.textures[&surface].clone()
+    }
+}
+
 pub fn initialize_blocks(world: &mut World, blocks: &Vec<Block>) {
     let mesh = world
         .exec(|loader: AssetLoaderSystemData<'_, Mesh>| loader.load("mesh/cube.obj", ObjFormat, ()));
     
     let mat_default = world.read_resource::<MaterialDefaults>().0.clone();
 
-    // Load all materials
-    let dirt_mat = get_mat(world, "dirt.png", mat_default.clone());
-    let grass_mat = get_mat(world, "grass.png", mat_default.clone());
-    let gravel_mat = get_mat(world, "gravel.png", mat_default.clone());
-    let snow_mat = get_mat(world, "snow.png", mat_default.clone());
-    let stone_rough_mat = get_mat(world, "stone_rough.png", mat_default.clone());
-    let stone_smooth_mat = get_mat(world, "stone_smooth.png", mat_default.clone());
-    
+    // Load all textures and materials
+    let mut materials = HashMap::new();
+    let mut textures = HashMap::new();
+    for surface in BlockSurface::ALL.iter() {
+        let texture = get_texture(world, surface.texture_file());
+        let material = texture_mat(world, texture.clone(), mat_default.clone());
+        materials.insert(*surface, material);
+        textures.insert(*surface, texture);
+    }
+
+    world.insert(BlockAssets {
+        mesh,
+        materials,
+        textures,
+    });
+    spawn_blocks(world, blocks);
+}
+
+/// Create entities for the blocks with the loaded `BlockAssets`, and a new grid of them
+pub fn spawn_blocks(world: &mut World, blocks: &[Block]) {
+    let (mesh, materials) = {
+        let assets = world.read_resource::<BlockAssets>();
+        (assets.mesh.clone(), assets.materials.clone())
+    };
+    let mut grid = BlockGrid::default();
+
     for block in blocks.iter() {
+        // Only one block per cell
+        if grid.contains(block.cell()) {
+            continue;
+        }
+
         let mut transform = Transform::default();
         transform.append_translation_xyz(block.x, block.y, block.z);
 
-        let material = match block.surface {
-            BlockSurface::Dirt => dirt_mat.clone(),
-            BlockSurface::Grass => grass_mat.clone(),
-            BlockSurface::Gravel => gravel_mat.clone(),
-            BlockSurface::Snow => snow_mat.clone(),
-            BlockSurface::StoneRough => stone_rough_mat.clone(),
-            BlockSurface::StoneSmooth => stone_smooth_mat.clone()
-        };
-
-        world
+        let entity = world
             .create_entity()
             .with(block.clone())
             .with(mesh.clone())
-            .with(material.clone())
+            .with(materials[&block.surface].clone())
             .with(transform)
             .build();
+        grid.insert(block.cell(), entity, block.surface);
     }
+
+    world.insert(grid);
 }
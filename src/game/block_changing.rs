@@ -1,12 +1,15 @@
 use amethyst::{
     assets::Handle,
+    controls::HideCursor,
     core::{
+        geometry::Ray,
         math::{Point2, Point3, Vector2, Vector3},
         Transform,
     },
     derive::SystemDesc,
     ecs::{
-        Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, WriteStorage,
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, Write,
+        WriteExpect, WriteStorage,
     },
     input::{InputEvent, StringBindings},
     renderer::{ActiveCamera, Camera, Material, Mesh},
@@ -15,11 +18,106 @@ use amethyst::{
     winit::MouseButton,
 };
 
-use super::{Block, Player, BLOCK_SIZE_FROM_CENTER};
+use super::{
+    Block, BlockAssets, BlockGrid, BlockHistory, CellEdit, GameMode, Insertion, Inventory,
+    ItemStack, Player, PlayerCamera, ScheduledEvents, UndoStack, WorldTime, BLOCK_SIZE_FROM_CENTER,
+    HOTBAR_SIZE,
+};
 
 /// How low the player can reach to break and place blocks
 pub const PLAYER_REACH: f32 = 5.0;
 
+/// Ray from the camera through the crosshair in the middle of the screen
+pub fn crosshair_ray(
+    camera: &Camera,
+    camera_transform: &Transform,
+    screen_dimensions: &ScreenDimensions,
+) -> Ray<f32> {
+    camera.screen_ray(
+        Point2::new(
+            screen_dimensions.width() / 2.0,
+            screen_dimensions.height() / 2.0,
+        ),
+        Vector2::new(screen_dimensions.width(), screen_dimensions.height()),
+        camera_transform,
+    )
+}
+
+/// Nearest block the ray hits within `reach`, with its distance from the ray origin
+pub fn raycast_block<'a>(
+    ray: &Ray<f32>,
+    reach: f32,
+    blocks: impl Iterator<Item = (Entity, &'a Block)>,
+) -> Option<(&'a Block, f32, Entity)> {
+    // Nearest block and distance from camera
+    let mut nearest_block: Option<(&Block, f32, Entity)> = None;
+
+    for (entity, block) in blocks {
+        // Raycasting using AABB (axis aligned bounding box)
+        // Inspiration from https://gdbooks.gitbooks.io/3dcollisions/content/Chapter3/raycast_aabb.html
+        let min_x = block.x - BLOCK_SIZE_FROM_CENTER;
+        let max_x = block.x + BLOCK_SIZE_FROM_CENTER;
+        let min_y = block.y - BLOCK_SIZE_FROM_CENTER;
+        let max_y = block.y + BLOCK_SIZE_FROM_CENTER;
+        let min_z = block.z - BLOCK_SIZE_FROM_CENTER;
+        let max_z = block.z + BLOCK_SIZE_FROM_CENTER;
+        let t1 = (min_x - ray.origin.x) / ray.direction.x;
+        let t2 = (max_x - ray.origin.x) / ray.direction.x;
+        let t3 = (min_y - ray.origin.y) / ray.direction.y;
+        let t4 = (max_y - ray.origin.y) / ray.direction.y;
+        let t5 = (min_z - ray.origin.z) / ray.direction.z;
+        let t6 = (max_z - ray.origin.z) / ray.direction.z;
+
+        let tmin = f32::max(
+            f32::max(f32::min(t1, t2), f32::min(t3, t4)),
+            f32::min(t5, t6),
+        );
+        let tmax = f32::min(
+            f32::min(f32::max(t1, t2), f32::max(t3, t4)),
+            f32::max(t5, t6),
+        );
+        // if tmax < 0, ray (line) is intersecting AABB, but whole AABB is behind us
+        if tmax < 0.0 {
+            continue;
+        }
+        // if tmin > tmax, ray doesn't intersect AABB
+        if tmin > tmax {
+            continue;
+        }
+        // t-value for ray to block collision point
+        let dist = if tmin < 0.0 { tmax } else { tmin };
+        // if block is further away than a certain threshold
+        if dist > reach {
+            continue;
+        }
+
+        // update nearest block
+        if let Some((_, block_dist, _)) = nearest_block {
+            if dist < block_dist {
+                nearest_block = Some((block, dist, entity));
+            }
+        } else {
+            nearest_block = Some((block, dist, entity));
+        }
+    }
+
+    nearest_block
+}
+
+/// Face of the block the ray hits at `dist`, as the axis index and the sign of its normal
+pub fn hit_face(ray: &Ray<f32>, block: &Block, dist: f32) -> (usize, f32) {
+    // took a long time to debug:
+    // fn at_distance returns origin - (direction * dist), fixed with negative dist
+    let click_point = ray.at_distance(-dist);
+    let block_point = Point3::new(block.x, block.y, block.z);
+    let hit_direction: Vector3<f32> = click_point - block_point;
+
+    // index of largest absolute component
+    let index = hit_direction.iamax();
+    // one times sign (+/-) of largest abs component
+    (index, hit_direction[index].signum())
+}
+
 #[derive(SystemDesc)]
 #[system_desc(name(MouseRaycastSystemDesc))]
 pub struct MouseRaycastSystem {
@@ -40,11 +138,21 @@ impl<'s> System<'s> for MouseRaycastSystem {
         WriteStorage<'s, Handle<Mesh>>,
         WriteStorage<'s, Handle<Material>>,
         WriteStorage<'s, Transform>,
-        WriteStorage<'s, Player>,
+        ReadStorage<'s, Player>,
+        WriteStorage<'s, Inventory>,
         ReadStorage<'s, Camera>,
+        ReadStorage<'s, PlayerCamera>,
         Read<'s, ActiveCamera>,
         ReadExpect<'s, ScreenDimensions>,
         Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Write<'s, BlockGrid>,
+        ReadExpect<'s, BlockAssets>,
+        Read<'s, GameMode>,
+        Read<'s, HideCursor>,
+        Read<'s, WorldTime>,
+        Write<'s, BlockHistory>,
+        WriteExpect<'s, UndoStack>,
+        Write<'s, ScheduledEvents>,
     );
 
     fn run(
@@ -55,14 +163,38 @@ impl<'s> System<'s> for MouseRaycastSystem {
             mut meshes,
             mut materials,
             mut locals,
-            mut players,
+            players,
+            mut inventories,
             cameras,
+            player_cameras,
             active_camera,
             screen_dimensions,
             events,
+            mut grid,
+            block_assets,
+            game_mode,
+            hide_cursor,
+            world_time,
+            mut history,
+            mut undo,
+            mut scheduled,
         ): Self::SystemData,
     ) {
+        // In third person the camera is further away from the blocks the player can reach
+        let reach = PLAYER_REACH
+            + (&player_cameras)
+                .join()
+                .map(|camera| camera.distance)
+                .fold(0., f32::max);
+
+        // Dead players cannot change blocks, and clicks go to the UI while the cursor is shown
+        let alive = (&players).join().any(|player| !player.is_dead());
+
         for event in events.read(&mut self.event_reader) {
+            if !alive || !hide_cursor.hide {
+                continue;
+            }
+
             // if left or right mouse button is pressed
             if let InputEvent::MouseButtonPressed(
                 button @ (MouseButton::Left | MouseButton::Right | MouseButton::Middle),
@@ -75,82 +207,62 @@ impl<'s> System<'s> for MouseRaycastSystem {
                     .and_then(|a| camera_join.get(a, &entities))
                     .or_else(|| camera_join.next())
                 {
-                    // Project a ray from the camera
-                    let ray = camera.screen_ray(
-                        // Middle of screen, crosshair position
-                        Point2::new(
-                            screen_dimensions.width() / 2.0,
-                            screen_dimensions.height() / 2.0,
-                        ),
-                        Vector2::new(screen_dimensions.width(), screen_dimensions.height()),
-                        camera_transform,
-                    );
-                    // Nearest block and distance from camera
-                    let mut nearest_block: Option<(&Block, f32, Entity)> = None;
-
-                    for (entity, block) in (&entities, &blocks).join() {
-                        // Raycasting using AABB (axis aligned bounding box)
-                        // Inspiration from https://gdbooks.gitbooks.io/3dcollisions/content/Chapter3/raycast_aabb.html
-                        let min_x = block.x - BLOCK_SIZE_FROM_CENTER;
-                        let max_x = block.x + BLOCK_SIZE_FROM_CENTER;
-                        let min_y = block.y - BLOCK_SIZE_FROM_CENTER;
-                        let max_y = block.y + BLOCK_SIZE_FROM_CENTER;
-                        let min_z = block.z - BLOCK_SIZE_FROM_CENTER;
-                        let max_z = block.z + BLOCK_SIZE_FROM_CENTER;
-                        let t1 = (min_x - ray.origin.x) / ray.direction.x;
-                        let t2 = (max_x - ray.origin.x) / ray.direction.x;
-                        let t3 = (min_y - ray.origin.y) / ray.direction.y;
-                        let t4 = (max_y - ray.origin.y) / ray.direction.y;
-                        let t5 = (min_z - ray.origin.z) / ray.direction.z;
-                        let t6 = (max_z - ray.origin.z) / ray.direction.z;
-
-                        let tmin = f32::max(
-                            f32::max(f32::min(t1, t2), f32::min(t3, t4)),
-                            f32::min(t5, t6),
-                        );
-                        let tmax = f32::min(
-                            f32::min(f32::max(t1, t2), f32::max(t3, t4)),
-                            f32::max(t5, t6),
-                        );
-                        // if tmax < 0, ray (line) is intersecting AABB, but whole AABB is behind us
-                        if tmax < 0.0 {
-                            continue;
-                        }
-                        // if tmin > tmax, ray doesn't intersect AABB
-                        if tmin > tmax {
-                            continue;
-                        }
-                        // t-value for ray to block collision point
-                        let dist = if tmin < 0.0 { tmax } else { tmin };
-                        // if block is further away than a certain threshold
-                        if dist > PLAYER_REACH {
-                            continue;
-                        }
-
-                        // update nearest block
-                        if let Some((_, block_dist, _)) = nearest_block {
-                            if dist < block_dist {
-                                nearest_block = Some((block, dist, entity));
-                            }
-                        } else {
-                            nearest_block = Some((block, dist, entity));
-                        }
-                    }
+                    let ray = crosshair_ray(camera, camera_transform, &screen_dimensions);
+                    let nearest_block = raycast_block(&ray, reach, (&entities, &blocks).join());
 
                     // If left mouse is pressed (destroy block)
                     if let MouseButton::Left = button {
                         // destroy nearest block (if any)
-                        if let Some((_, _, entity)) = nearest_block {
-                            entities.delete(entity).unwrap();
+                        if let Some((block, _, entity)) = nearest_block {
+                            // Survival collects the block, so it is not broken if it would not fit
+                            let fits = *game_mode == GameMode::Creative
+                                || (&inventories)
+                                    .join()
+                                    .all(|inventory| inventory.has_room_for(block.surface));
+                            if block.surface.is_breakable() && fits {
+                                grid.remove(block.cell());
+                                entities.delete(entity).unwrap();
+                                let edit = CellEdit {
+                                    time: world_time.now,
+                                    cell: block.cell(),
+                                    before: Some(block.surface),
+                                    after: None,
+                                    dropped: None,
+                                };
+                                let insertion =
+                                    history.record(edit.time, edit.cell, edit.before, edit.after);
+                                if let Insertion::Recorded { dropped } = insertion {
+                                    scheduled.schedule_behaviours(edit.time, edit.cell, edit.after);
+                                    undo.push(vec![CellEdit { dropped, ..edit }]);
+                                }
+
+                                // Collect the block
+                                if *game_mode == GameMode::Survival {
+                                    for inventory in (&mut inventories).join() {
+                                        inventory.add(block.surface, 1);
+                                    }
+                                }
+                            }
                         }
                     }
 
-                    // If middle mouse clicked (store block material)
+                    // If middle mouse clicked (pick block)
                     if let MouseButton::Middle = button {
-                        if let Some((block, _, entity)) = nearest_block {
-                            for player in (&mut players).join() {
-                                player.current_block =
-                                    Some((materials.get(entity).unwrap().clone(), block.surface));
+                        if let Some((block, _, _)) = nearest_block {
+                            for inventory in (&mut inventories).join() {
+                                if let Some(slot) = inventory.find(block.surface) {
+                                    // Blocks outside the hotbar are swapped into the selected slot
+                                    if slot < HOTBAR_SIZE {
+                                        inventory.selected = slot;
+                                    } else {
+                                        let selected = inventory.selected;
+                                        inventory.slots.swap(slot, selected);
+                                    }
+                                } else if *game_mode == GameMode::Creative {
+                                    let selected = inventory.selected;
+                                    inventory.slots[selected] =
+                                        Some(ItemStack::full(block.surface));
+                                }
                             }
                         }
                     }
@@ -158,33 +270,28 @@ impl<'s> System<'s> for MouseRaycastSystem {
                     // If right mouse is pressed (place block)
                     if let MouseButton::Right = button {
                         // place block on top of
-                        if let Some((block, dist, entity)) = nearest_block {
-                            // Get material and surface stored in player (if they have picked one using middle click)
+                        if let Some((block, dist, _)) = nearest_block {
+                            // Get surface in the selected inventory slot
                             let current_block = {
                                 let mut block = None;
-                                for player in (&mut players).join() {
-                                    block = player.current_block.as_ref();
+                                for inventory in (&inventories).join() {
+                                    block = inventory.selected_stack().map(|stack| stack.surface);
                                 }
                                 block
                             };
 
-                            // If there is a material place the block
-                            if let Some((material, surface)) = current_block {
-                                let mesh = meshes.get(entity).unwrap(); // Get mesh of nearest block (easy way to get block, could probably be better)
-
-                                // took a long time to debug:
-                                // fn at_distance returns origin - (direction * dist), fixed with negative dist
-                                let click_point = ray.at_distance(-dist);
+                            // If there is a block in the slot place it
+                            if let Some(surface) = current_block {
+                                let (index, direction) = hit_face(&ray, block, dist);
                                 let mut block_point = Point3::new(block.x, block.y, block.z);
-                                let hit_direction: Vector3<f32> = click_point - block_point;
-
-                                // index of largest absolute component
-                                let index = hit_direction.iamax();
-                                // one times sign (+/-) of largest abs component
-                                let direction = hit_direction[index].signum();
                                 // placed block destination is 1 in corresponding direction
                                 block_point[index] += direction;
                                 // block_point is now placed block position
+                                let cell =
+                                    BlockGrid::cell_at(block_point.x, block_point.y, block_point.z);
+                                if grid.contains(cell) {
+                                    continue;
+                                }
 
                                 // TODO: if block to be placed collides with player
 
@@ -195,21 +302,42 @@ impl<'s> System<'s> for MouseRaycastSystem {
                                     block_point.z,
                                 );
 
-                                entities
+                                let placed = entities
                                     .build_entity()
                                     .with(
                                         Block::new(
                                             block_point.x,
                                             block_point.y,
                                             block_point.z,
-                                            surface.clone(),
+                                            surface,
                                         ),
                                         &mut blocks,
                                     )
                                     .with(transform, &mut locals)
-                                    .with(mesh.clone(), &mut meshes)
-                                    .with(material.clone(), &mut materials)
+                                    .with(block_assets.mesh.clone(), &mut meshes)
+                                    .with(block_assets.material(surface), &mut materials)
                                     .build();
+                                grid.insert(cell, placed, surface);
+                                let edit = CellEdit {
+                                    time: world_time.now,
+                                    cell,
+                                    before: None,
+                                    after: Some(surface),
+                                    dropped: None,
+                                };
+                                let insertion =
+                                    history.record(edit.time, edit.cell, edit.before, edit.after);
+                                if let Insertion::Recorded { dropped } = insertion {
+                                    scheduled.schedule_behaviours(edit.time, edit.cell, edit.after);
+                                    undo.push(vec![CellEdit { dropped, ..edit }]);
+                                }
+
+                                // Placing uses up a block except in creative
+                                if *game_mode == GameMode::Survival {
+                                    for inventory in (&mut inventories).join() {
+                                        inventory.take_selected();
+                                    }
+                                }
                             }
                         }
                     }
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use amethyst::ecs::Entity;
+
+use super::{chunk_of, BlockSurface, Chunk};
+
+/// Integer position of a block in the world
+pub type Cell = (i32, i32, i32);
+
+/// Spatial hash of all blocks in the world, so that lookups only touch nearby cells
+#[derive(Default)]
+pub struct BlockGrid {
+    cells: HashMap<Cell, (Entity, BlockSurface)>,
+    y_bounds: Option<(i32, i32)>, // Lowest and highest y a block has been in
+    chunk_blocks: HashMap<Chunk, usize>, // Number of blocks in every chunk holding any
+}
+
+impl BlockGrid {
+    /// Cell containing the point
+    pub fn cell_at(x: f32, y: f32, z: f32) -> Cell {
+        (x.round() as i32, y.round() as i32, z.round() as i32)
+    }
+
+    pub fn get(&self, cell: Cell) -> Option<Entity> {
+        self.cells.get(&cell).map(|(entity, _)| *entity)
+    }
+
+    pub fn surface(&self, cell: Cell) -> Option<BlockSurface> {
+        self.cells.get(&cell).map(|(_, surface)| *surface)
+    }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.cells.contains_key(&cell)
+    }
+
+    /// Whether the cell holds a block that cannot be walked through
+    pub fn is_solid(&self, cell: Cell) -> bool {
+        self.surface(cell).map_or(false, |surface| surface.is_solid())
+    }
+
+    /// Returns the entity previously in the cell, if any
+    pub fn insert(&mut self, cell: Cell, entity: Entity, surface: BlockSurface) -> Option<Entity> {
+        let y = cell.1;
+        self.y_bounds = Some(match self.y_bounds {
+            Some((low, high)) => (low.min(y), high.max(y)),
+            None => (y, y),
+        });
+        let previous = self
+            .cells
+            .insert(cell, (entity, surface))
+            .map(|(entity, _)| entity);
+        if previous.is_none() {
+            *self.chunk_blocks.entry(chunk_of(cell)).or_default() += 1;
+        }
+        previous
+    }
+
+    pub fn remove(&mut self, cell: Cell) -> Option<Entity> {
+        let removed = self.cells.remove(&cell).map(|(entity, _)| entity);
+        if removed.is_some() {
+            let chunk = chunk_of(cell);
+            if let Some(count) = self.chunk_blocks.get_mut(&chunk) {
+                *count -= 1;
+                if *count == 0 {
+                    self.chunk_blocks.remove(&chunk);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Number of chunks with at least one block
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_blocks.len()
+    }
+
+    /// Y of the highest block in the column
+    pub fn highest_in_column(&self, x: i32, z: i32) -> Option<i32> {
+        let (low, high) = self.y_bounds?;
+        (low..=high).rev().find(|&y| self.cells.contains_key(&(x, y, z)))
+    }
+
+    /// All solid cells whose block overlaps the box between the corners `min` and `max`
+    pub fn solid_cells_in_box(&self, min: [f32; 3], max: [f32; 3]) -> Vec<Cell> {
+        let (x0, y0, z0) = Self::cell_at(min[0], min[1], min[2]);
+        let (x1, y1, z1) = Self::cell_at(max[0], max[1], max[2]);
+
+        let mut found = Vec::new();
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                for z in z0..=z1 {
+                    if self.is_solid((x, y, z)) {
+                        found.push((x, y, z));
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use amethyst::{prelude::WorldExt, shred::World};
+
+    use super::*;
+    use crate::game::{generate_terrain, Block, HEAD_HEIGHT, HEIGHT, PLAYER_SIZE_FROM_CENTER};
+
+    /// Time taken to find the solid cells in every box, and how many were found
+    fn time_queries(
+        boxes: &[([f32; 3], [f32; 3])],
+        query: impl Fn([f32; 3], [f32; 3]) -> usize,
+    ) -> (Duration, usize) {
+        let start = Instant::now();
+        let found = boxes.iter().map(|(min, max)| query(*min, *max)).sum();
+        (start.elapsed(), found)
+    }
+
+    /// Collision candidates of a player standing on every 4th column of the default
+    /// 128x128 world, found with the grid and by iterating over every block as before.
+    /// Run with `cargo test --release -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore]
+    fn grid_and_full_iteration_find_the_same_blocks() {
+        let world = World::new();
+        let mut grid = BlockGrid::default();
+        let mut blocks: Vec<Block> = Vec::new();
+        for block in generate_terrain(0) {
+            if !grid.contains(block.cell()) {
+                grid.insert(block.cell(), world.entities().create(), block.surface);
+                blocks.push(block);
+            }
+        }
+
+        let mut boxes = Vec::new();
+        for x in (-64..64).step_by(4) {
+            for z in (-64..64).step_by(4) {
+                if let Some(top) = grid.highest_in_column(x, z) {
+                    let (x, eyes, z) = (x as f32, top as f32 + 0.5 + HEIGHT, z as f32);
+                    boxes.push((
+                        [
+                            x - PLAYER_SIZE_FROM_CENTER,
+                            eyes - HEIGHT,
+                            z - PLAYER_SIZE_FROM_CENTER,
+                        ],
+                        [
+                            x + PLAYER_SIZE_FROM_CENTER,
+                            eyes + HEAD_HEIGHT,
+                            z + PLAYER_SIZE_FROM_CENTER,
+                        ],
+                    ));
+                }
+            }
+        }
+
+        let (grid_time, from_grid) =
+            time_queries(&boxes, |min, max| grid.solid_cells_in_box(min, max).len());
+        let (iteration_time, from_iteration) = time_queries(&boxes, |min, max| {
+            let (x0, y0, z0) = BlockGrid::cell_at(min[0], min[1], min[2]);
+            let (x1, y1, z1) = BlockGrid::cell_at(max[0], max[1], max[2]);
+            blocks
+                .iter()
+                .filter(|block| {
+                    let (x, y, z) = block.cell();
+                    block.surface.is_solid()
+                        && (x0..=x1).contains(&x)
+                        && (y0..=y1).contains(&y)
+                        && (z0..=z1).contains(&z)
+                })
+                .count()
+        });
+
+        println!(
+            "{} blocks, {} queries: grid {:?}, full iteration {:?}",
+            blocks.len(),
+            boxes.len(),
+            grid_time,
+            iteration_time
+        );
+        assert_eq!(from_grid, from_iteration);
+    }
+}
@@ -0,0 +1,442 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+
+use super::{BlockSurface, Cell};
+
+/// Width of the cubes the history is grouped into, so region queries skip far away cells
+pub const CHUNK_SIZE: i32 = 16;
+
+/// Position of a chunk, cell coordinates divided by `CHUNK_SIZE`
+pub type Chunk = (i32, i32, i32);
+
+pub fn chunk_of(cell: Cell) -> Chunk {
+    (
+        cell.0.div_euclid(CHUNK_SIZE),
+        cell.1.div_euclid(CHUNK_SIZE),
+        cell.2.div_euclid(CHUNK_SIZE),
+    )
+}
+
+/// A cell becoming a block (`Some`) or empty (`None`) at a point in time
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TimedBlock {
+    pub time: i32,
+    pub surface: Option<BlockSurface>,
+}
+
+/// A change found by a region query
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockChange {
+    pub time: i32,
+    pub cell: Cell,
+    pub surface: Option<BlockSurface>,
+}
+
+/// What `CellHistory::insert` did with an event
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Insertion {
+    /// The event did not change the state at its time, so it was left out
+    Skipped,
+    /// The event was added, and the next event was dropped if it became redundant
+    Recorded { dropped: Option<TimedBlock> },
+}
+
+impl Insertion {
+    pub fn is_recorded(&self) -> bool {
+        matches!(self, Self::Recorded { .. })
+    }
+}
+
+/// Timeline of one cell: what was generated there and every change since
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CellHistory {
+    pub genesis: Option<BlockSurface>,
+    events: Vec<TimedBlock>, // Sorted by time, events at the same time in the order they happened
+}
+
+impl CellHistory {
+    pub fn new(genesis: Option<BlockSurface>) -> Self {
+        Self {
+            genesis,
+            events: Vec::new(),
+        }
+    }
+
+    /// Block in the cell at `time`, set by the latest event at or before it
+    pub fn state_at(&self, time: i32) -> Option<BlockSurface> {
+        match self.events.partition_point(|event| event.time <= time) {
+            0 => self.genesis,
+            i => self.events[i - 1].surface,
+        }
+    }
+
+    /// Add an event after all others at the same time. It holds until the next
+    /// event, so an edit in the past changes every later state up to that event.
+    ///
+    /// Conflicts with later events are resolved by keeping the history minimal:
+    /// - An event that does not change the state at its time is not recorded.
+    /// - A later event always takes over from an earlier one, so placing a block
+    ///   before a later place or break only lasts until that event.
+    /// - The next event is dropped when it would set the state the new event already
+    ///   set, e.g. breaking a block before the later break of the same block.
+    ///
+    /// The dropped event is returned so the insertion can be taken back.
+    pub fn insert(&mut self, time: i32, surface: Option<BlockSurface>) -> Insertion {
+        if self.state_at(time) == surface {
+            return Insertion::Skipped;
+        }
+
+        let i = self.events.partition_point(|event| event.time <= time);
+        self.events.insert(i, TimedBlock { time, surface });
+        let dropped = if self.events.get(i + 1).map(|next| next.surface) == Some(surface) {
+            Some(self.events.remove(i + 1))
+        } else {
+            None
+        };
+        Insertion::Recorded { dropped }
+    }
+
+    /// Take back an insertion: remove the last event at `time` if it set `surface`,
+    /// and put back the event the insertion dropped. Returns whether it did.
+    pub fn remove_last_at(
+        &mut self,
+        time: i32,
+        surface: Option<BlockSurface>,
+        dropped: Option<TimedBlock>,
+    ) -> bool {
+        let i = self.events.partition_point(|event| event.time <= time);
+        if i == 0 || self.events[i - 1] != (TimedBlock { time, surface }) {
+            return false;
+        }
+        self.events.remove(i - 1);
+
+        // It was later than the removed event and first at its time
+        if let Some(dropped) = dropped {
+            let j = self
+                .events
+                .partition_point(|event| event.time < dropped.time);
+            self.events.insert(j, dropped);
+        }
+        true
+    }
+
+    pub fn events(&self) -> &[TimedBlock] {
+        &self.events
+    }
+
+    /// Events with `t0 < time <= t1`
+    pub fn events_between(&self, t0: i32, t1: i32) -> &[TimedBlock] {
+        let start = self.events.partition_point(|event| event.time <= t0);
+        let end = self.events.partition_point(|event| event.time <= t1);
+        &self.events[start..end.max(start)]
+    }
+}
+
+/// Cells of one chunk, shared between copies of the history until one of them changes it
+type ChunkCells = Arc<HashMap<Cell, CellHistory>>;
+
+/// History of every changed cell in the world. Cells that were never changed
+/// are left out and keep their generated block at all times.
+///
+/// Cloning is cheap, as clones share the chunks neither of them has changed since.
+#[derive(Clone, Default)]
+pub struct BlockHistory {
+    chunks: HashMap<Chunk, ChunkCells>,
+    dirty: Vec<Cell>, // Cells changed since the last call to take_dirty
+}
+
+/// How a history is written to `saves/<name>/history.ron`
+#[derive(Default, Deserialize, Serialize)]
+struct SavedHistory {
+    cells: Vec<(Cell, CellHistory)>,
+}
+
+impl BlockHistory {
+    /// Record that `cell` became `after` at `time`. `before` is the block the cell
+    /// held when it was changed, which is its generated block if it has no history yet.
+    /// See `CellHistory::insert` for how it combines with later changes.
+    pub fn record(
+        &mut self,
+        time: i32,
+        cell: Cell,
+        before: Option<BlockSurface>,
+        after: Option<BlockSurface>,
+    ) -> Insertion {
+        let chunk = self.chunks.entry(chunk_of(cell)).or_default();
+        let insertion = Arc::make_mut(chunk)
+            .entry(cell)
+            .or_insert_with(|| CellHistory::new(before))
+            .insert(time, after);
+        if insertion.is_recorded() {
+            self.dirty.push(cell);
+        }
+        insertion
+    }
+
+    /// Take back a change recorded at `time` that made the cell `surface`, if it is
+    /// still the last change at that time. `dropped` is the event its recording dropped.
+    pub fn revert(
+        &mut self,
+        time: i32,
+        cell: Cell,
+        surface: Option<BlockSurface>,
+        dropped: Option<TimedBlock>,
+    ) -> bool {
+        let reverted = self
+            .chunks
+            .get_mut(&chunk_of(cell))
+            .and_then(|cells| Arc::make_mut(cells).get_mut(&cell))
+            .is_some_and(|history| history.remove_last_at(time, surface, dropped));
+        if reverted {
+            self.dirty.push(cell);
+        }
+        reverted
+    }
+
+    pub fn cell(&self, cell: Cell) -> Option<&CellHistory> {
+        self.chunks.get(&chunk_of(cell))?.get(&cell)
+    }
+
+    /// Block in `cell` at `time`, or `None` if the cell has no history to answer from
+    pub fn state_at(&self, cell: Cell, time: i32) -> Option<Option<BlockSurface>> {
+        self.cell(cell).map(|history| history.state_at(time))
+    }
+
+    /// Changes to cells in the box between `min` and `max` with `t0 < time <= t1`, oldest first
+    pub fn changes_in_region(&self, min: Cell, max: Cell, t0: i32, t1: i32) -> Vec<BlockChange> {
+        let (low, high) = (chunk_of(min), chunk_of(max));
+        let inside = |cell: &Cell| {
+            (min.0..=max.0).contains(&cell.0)
+                && (min.1..=max.1).contains(&cell.1)
+                && (min.2..=max.2).contains(&cell.2)
+        };
+
+        let mut changes = Vec::new();
+        for (chunk, cells) in self.chunks.iter() {
+            if chunk.0 < low.0
+                || chunk.0 > high.0
+                || chunk.1 < low.1
+                || chunk.1 > high.1
+                || chunk.2 < low.2
+                || chunk.2 > high.2
+            {
+                continue;
+            }
+            for (cell, history) in cells.iter().filter(|(cell, _)| inside(cell)) {
+                changes.extend(
+                    history
+                        .events_between(t0, t1)
+                        .iter()
+                        .map(|event| BlockChange {
+                            time: event.time,
+                            cell: *cell,
+                            surface: event.surface,
+                        }),
+                );
+            }
+        }
+        // Stable, so events at the same time in one cell stay in order
+        changes.sort_by_key(|change| change.time);
+        changes
+    }
+
+    /// Cells whose history changed since this was last called
+    pub fn take_dirty(&mut self) -> Vec<Cell> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (&Cell, &CellHistory)> {
+        self.chunks.values().flat_map(|cells| cells.iter())
+    }
+
+    /// Go back to an earlier copy of the history. Cells changed since the copy was
+    /// taken keep an empty history, so they show their generated block again.
+    pub fn restore(&mut self, earlier: &BlockHistory) {
+        let mut restored = earlier.clone();
+        for (cell, history) in self.cells() {
+            if restored.cell(*cell).is_none() {
+                let chunk = restored.chunks.entry(chunk_of(*cell)).or_default();
+                Arc::make_mut(chunk).insert(*cell, CellHistory::new(history.genesis));
+            }
+        }
+
+        restored.dirty = self
+            .cells()
+            .chain(restored.cells())
+            .map(|(cell, _)| *cell)
+            .collect();
+        *self = restored;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let saved = SavedHistory {
+            cells: self
+                .cells()
+                .map(|(cell, history)| (*cell, history.clone()))
+                .collect(),
+        };
+        fs::create_dir_all(path.parent().unwrap())
+            .map_err(|e| e.to_string())
+            .and_then(|_| saved.write(path).map_err(|e| e.to_string()))
+    }
+
+    /// Load a saved history, with every cell marked as changed
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let saved = SavedHistory::load(path).map_err(|e| e.to_string())?;
+        let mut history = Self::default();
+        for (cell, cell_history) in saved.cells {
+            let chunk = history.chunks.entry(chunk_of(cell)).or_default();
+            Arc::make_mut(chunk).insert(cell, cell_history);
+            history.dirty.push(cell);
+        }
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BlockSurface::{Dirt, Grass, Snow};
+
+    #[test]
+    fn state_at_follows_the_latest_event() {
+        let mut history = CellHistory::new(Some(Grass));
+        history.insert(10, None);
+        history.insert(20, Some(Dirt));
+
+        assert_eq!(history.state_at(-5), Some(Grass));
+        assert_eq!(history.state_at(9), Some(Grass));
+        assert_eq!(history.state_at(10), None);
+        assert_eq!(history.state_at(19), None);
+        assert_eq!(history.state_at(20), Some(Dirt));
+        assert_eq!(history.state_at(100), Some(Dirt));
+    }
+
+    #[test]
+    fn events_at_the_same_time_keep_their_order() {
+        let mut history = CellHistory::new(Some(Grass));
+        history.insert(5, None);
+        history.insert(5, Some(Dirt));
+        history.insert(5, Some(Snow));
+
+        let surfaces: Vec<_> = history.events().iter().map(|event| event.surface).collect();
+        assert_eq!(surfaces, vec![None, Some(Dirt), Some(Snow)]);
+        assert_eq!(history.state_at(5), Some(Snow));
+    }
+
+    #[test]
+    fn events_between_excludes_the_start() {
+        let mut history = CellHistory::new(Some(Grass));
+        history.insert(10, None);
+        history.insert(20, Some(Dirt));
+        history.insert(30, None);
+
+        let times = |t0, t1| -> Vec<i32> {
+            history
+                .events_between(t0, t1)
+                .iter()
+                .map(|event| event.time)
+                .collect()
+        };
+        assert_eq!(times(10, 30), vec![20, 30]);
+        assert_eq!(times(9, 20), vec![10, 20]);
+        assert_eq!(times(30, 100), Vec::<i32>::new());
+        assert_eq!(times(20, 10), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn a_later_event_takes_over_from_an_earlier_one() {
+        // Placing dirt before a later break lasts until that break
+        let mut history = CellHistory::new(None);
+        history.insert(10, Some(Grass));
+        history.insert(20, None);
+        assert!(history.insert(5, Some(Dirt)).is_recorded());
+
+        assert_eq!(history.state_at(5), Some(Dirt));
+        assert_eq!(history.state_at(10), Some(Grass));
+        assert_eq!(history.state_at(20), None);
+    }
+
+    #[test]
+    fn a_redundant_next_event_is_dropped() {
+        // Breaking a block before its later break leaves a single break
+        let mut history = CellHistory::new(Some(Grass));
+        history.insert(10, None);
+        assert_eq!(
+            history.insert(5, None),
+            Insertion::Recorded {
+                dropped: Some(TimedBlock {
+                    time: 10,
+                    surface: None
+                })
+            }
+        );
+
+        assert_eq!(
+            history.events(),
+            &[TimedBlock {
+                time: 5,
+                surface: None
+            }]
+        );
+        assert_eq!(history.state_at(10), None);
+    }
+
+    #[test]
+    fn an_insert_that_changes_nothing_is_not_recorded() {
+        let mut history = CellHistory::new(Some(Grass));
+        assert_eq!(history.insert(5, Some(Grass)), Insertion::Skipped);
+        history.insert(10, None);
+        assert_eq!(history.insert(15, None), Insertion::Skipped);
+
+        assert_eq!(history.events().len(), 1);
+    }
+
+    #[test]
+    fn changes_in_region_are_inside_the_box_and_oldest_first() {
+        let mut history = BlockHistory::default();
+        history.record(30, (1, 1, 1), Some(Grass), None);
+        history.record(10, (2, 0, 2), None, Some(Dirt));
+        history.record(20, (CHUNK_SIZE + 3, 0, 0), None, Some(Snow));
+        history.record(15, (-1, 0, 0), Some(Grass), None);
+        history.record(50, (0, 0, 0), None, Some(Dirt));
+
+        let changes = history.changes_in_region((0, 0, 0), (CHUNK_SIZE + 3, 2, 2), 0, 40);
+        let found: Vec<(i32, Cell)> = changes
+            .iter()
+            .map(|change| (change.time, change.cell))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (10, (2, 0, 2)),
+                (20, (CHUNK_SIZE + 3, 0, 0)),
+                (30, (1, 1, 1)),
+            ]
+        );
+        assert_eq!(changes[0].surface, Some(Dirt));
+    }
+
+    #[test]
+    fn reverting_puts_back_the_dropped_event() {
+        let cell = (0, 0, 0);
+        let mut history = BlockHistory::default();
+        history.record(10, cell, Some(Grass), None);
+
+        // Breaking the block earlier drops the later break, undoing it brings it back
+        let dropped = match history.record(5, cell, Some(Grass), None) {
+            Insertion::Recorded { dropped } => dropped,
+            Insertion::Skipped => panic!("the break was not recorded"),
+        };
+        assert!(history.revert(5, cell, None, dropped));
+
+        assert_eq!(history.state_at(cell, 5), Some(Some(Grass)));
+        assert_eq!(history.state_at(cell, 10), Some(None));
+    }
+}
@@ -0,0 +1,615 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use amethyst::{
+    core::math::Vector3,
+    ecs::{Entity, Join},
+    prelude::WorldExt,
+    shred::World,
+};
+
+use super::{
+    block_at, generate_terrain, spawn_blocks, Block, BlockGrid, BlockHistory, BlockSurface,
+    EventKind, GameMode, Inventory, MovementMode, Player, ScheduledEvent, ScheduledEvents,
+    Snapshots, SpawnPoint, UndoStack, WorldInfo, WorldTime, INVENTORY_SIZE, MAX_STACK_SIZE,
+};
+
+/// Runs a command with its arguments, returning the message to show or what went wrong
+pub type CommandFn = fn(&mut World, &[&str]) -> Result<String, String>;
+
+pub struct Command {
+    pub usage: &'static str, // Arguments, shown with errors and in help
+    pub run: CommandFn,
+    pub completions: Option<fn() -> Vec<String>>, // Words the arguments can be completed to
+}
+
+/// Every command the console knows. Modules add their own with `register`.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, Command>,
+}
+
+impl CommandRegistry {
+    /// Registry with the commands every world has
+    pub fn with_builtin_commands() -> Self {
+        let mut registry = Self::default();
+        registry.register("help", "[command]", help);
+        registry.register("tp", "<x> <y> <z>", teleport);
+        registry.register_with_completions(
+            "setblock",
+            "<x> <y> <z> <block|air>",
+            set_block,
+            block_names,
+        );
+        registry.register_with_completions("give", "<block> [count]", give, block_names);
+        registry.register("time", "[time]", time);
+        registry.register_with_completions(
+            "schedule",
+            "<time> <x> <y> <z> <place <block>|remove|transform <from> <to>>",
+            schedule,
+            schedule_words,
+        );
+        registry.register_with_completions(
+            "gamemode",
+            "[survival|creative]",
+            game_mode,
+            game_mode_names,
+        );
+        registry.register("fly", "", fly);
+        registry.register("seed", "[seed]", seed);
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, usage: &'static str, run: CommandFn) {
+        self.commands.insert(
+            name.to_string(),
+            Command {
+                usage,
+                run,
+                completions: None,
+            },
+        );
+    }
+
+    pub fn register_with_completions(
+        &mut self,
+        name: &str,
+        usage: &'static str,
+        run: CommandFn,
+        completions: fn() -> Vec<String>,
+    ) {
+        self.commands.insert(
+            name.to_string(),
+            Command {
+                usage,
+                run,
+                completions: Some(completions),
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    /// Ways to finish the last word of `line`, as whole lines
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let (start, last) = match line.rfind(' ') {
+            Some(i) => line.split_at(i + 1),
+            None => ("", line),
+        };
+
+        let words: Vec<String> = if start.is_empty() {
+            self.commands.keys().cloned().collect()
+        } else {
+            let name = start.trim_start_matches('/').split_whitespace().next();
+            name.and_then(|name| self.commands.get(name))
+                .and_then(|command| command.completions)
+                .map_or(Vec::new(), |completions| completions())
+        };
+
+        words
+            .into_iter()
+            .filter(|word| word.starts_with(last.trim_start_matches('/')))
+            .map(|word| {
+                let slash = if start.is_empty() && last.starts_with('/') {
+                    "/"
+                } else {
+                    ""
+                };
+                format!("{}{}{}", start, slash, word)
+            })
+            .collect()
+    }
+}
+
+/// Run a line typed into the console, which may start with a `/`
+pub fn execute_command(world: &mut World, line: &str) -> Result<String, String> {
+    let mut words = line.trim().trim_start_matches('/').split_whitespace();
+    let name = match words.next() {
+        Some(name) => name,
+        None => return Err("Type a command, or help to list them".to_string()),
+    };
+    let args: Vec<&str> = words.collect();
+
+    let command = world
+        .read_resource::<CommandRegistry>()
+        .get(name)
+        .map(|command| (command.run, command.usage));
+    let (run, usage) =
+        command.ok_or_else(|| format!("Unknown command {}, type help to list them", name))?;
+    run(world, &args).map_err(|e| format!("{}\nUsage: {} {}", e, name, usage))
+}
+
+fn block_names() -> Vec<String> {
+    BlockSurface::ALL
+        .iter()
+        .filter(|surface| surface.is_breakable())
+        .map(|surface| surface.command_name())
+        .chain(std::iter::once("air".to_string()))
+        .collect()
+}
+
+fn schedule_words() -> Vec<String> {
+    ["place", "remove", "transform"]
+        .iter()
+        .map(|word| word.to_string())
+        .chain(block_names())
+        .collect()
+}
+
+fn game_mode_names() -> Vec<String> {
+    GameMode::ALL
+        .iter()
+        .map(|mode| mode.name().to_string())
+        .collect()
+}
+
+/// Argument `i`, called `name` in error messages
+fn argument<T: FromStr>(args: &[&str], i: usize, name: &str) -> Result<T, String> {
+    let arg = args.get(i).ok_or_else(|| format!("Missing {}", name))?;
+    arg.parse()
+        .map_err(|_| format!("{} is not a valid {}", arg, name))
+}
+
+fn expect_arguments(args: &[&str], min: usize, max: usize) -> Result<(), String> {
+    if args.len() < min {
+        Err("Too few arguments".to_string())
+    } else if args.len() > max {
+        Err("Too many arguments".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Block named by a command argument, `air` meaning no block. Bedrock cannot be named, as
+/// a placed one could never be broken again.
+fn block_argument(args: &[&str], i: usize) -> Result<Option<BlockSurface>, String> {
+    let arg = args.get(i).ok_or_else(|| "Missing block".to_string())?;
+    if arg.eq_ignore_ascii_case("air") {
+        return Ok(None);
+    }
+    let surface =
+        BlockSurface::from_command_name(arg).ok_or_else(|| format!("{} is not a block", arg))?;
+    if !surface.is_breakable() {
+        return Err(format!("{} cannot be placed or given", arg));
+    }
+    Ok(Some(surface))
+}
+
+fn with_player<T>(world: &mut World, f: impl FnOnce(&mut Player) -> T) -> Result<T, String> {
+    let mut players = world.write_storage::<Player>();
+    let player = (&mut players).join().next().ok_or("There is no player")?;
+    Ok(f(player))
+}
+
+fn help(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 0, 1)?;
+    let registry = world.read_resource::<CommandRegistry>();
+    match args.first() {
+        Some(name) => registry
+            .get(name)
+            .map(|command| format!("{} {}", name, command.usage))
+            .ok_or_else(|| format!("Unknown command {}", name)),
+        None => Ok(registry
+            .commands
+            .iter()
+            .map(|(name, command)| format!("{} {}", name, command.usage))
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
+
+fn teleport(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 3, 3)?;
+    let position = Vector3::new(
+        argument(args, 0, "x")?,
+        argument(args, 1, "y")?,
+        argument(args, 2, "z")?,
+    );
+    with_player(world, |player| player.teleport(position))?;
+    Ok(format!(
+        "Teleported to {} {} {}",
+        position.x, position.y, position.z
+    ))
+}
+
+fn set_block(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 4, 4)?;
+    let cell = (
+        argument(args, 0, "x")?,
+        argument(args, 1, "y")?,
+        argument(args, 2, "z")?,
+    );
+    let surface = block_argument(args, 3)?;
+
+    // Recorded like an edit by the player, the grid follows the history
+    let now = world.read_resource::<WorldTime>().now;
+    let before = block_at(
+        &world.read_resource::<BlockHistory>(),
+        &world.read_resource::<BlockGrid>(),
+        cell,
+        now,
+    );
+    if before == Some(BlockSurface::Bedrock) {
+        return Err("Bedrock cannot be replaced".to_string());
+    }
+    if world
+        .write_resource::<BlockHistory>()
+        .record(now, cell, before, surface)
+        .is_recorded()
+    {
+        world
+            .write_resource::<ScheduledEvents>()
+            .schedule_behaviours(now, cell, surface);
+    }
+    Ok(format!(
+        "Set {} {} {} to {}",
+        cell.0,
+        cell.1,
+        cell.2,
+        surface.map_or("air".to_string(), |surface| surface.command_name())
+    ))
+}
+
+fn give(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 1, 2)?;
+    let surface = block_argument(args, 0)?.ok_or("Air cannot be given")?;
+    let count: u32 = if args.len() > 1 {
+        argument(args, 1, "count")?
+    } else {
+        1
+    };
+    if count == 0 || count > INVENTORY_SIZE as u32 * MAX_STACK_SIZE {
+        return Err(format!("{} is not a valid count", count));
+    }
+
+    let mut inventories = world.write_storage::<Inventory>();
+    let inventory = (&mut inventories)
+        .join()
+        .next()
+        .ok_or("There is no inventory")?;
+    let given = count - inventory.add(surface, count);
+    Ok(format!("Gave {} {}", given, surface.command_name()))
+}
+
+/// There is no day and night, so the time changed is the world time of the time axis,
+/// the same as rewinding it with the time keys
+fn time(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 0, 1)?;
+    let mut world_time = world.write_resource::<WorldTime>();
+    let own_time = world_time.now - world_time.offset;
+    if args.is_empty() {
+        return Ok(format!(
+            "The time is {}, your own time is {}",
+            world_time.now, own_time
+        ));
+    }
+
+    // The time can only be moved behind the player's own time, as it follows their position
+    let target: i32 = argument(args, 0, "time")?;
+    if target > own_time {
+        return Err(format!("Cannot go ahead of your own time {}", own_time));
+    }
+    world_time.offset = target - own_time;
+    world_time.now = target;
+    Ok(format!("Set the time to {}", target))
+}
+
+/// Switch the game mode, which is kept with the world
+/// Schedules an event at a time on the time axis. It happens once the world time reaches
+/// it, or right away when the time has already passed.
+fn schedule(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 5, 7)?;
+    let time = argument(args, 0, "time")?;
+    let cell = (
+        argument(args, 1, "x")?,
+        argument(args, 2, "y")?,
+        argument(args, 3, "z")?,
+    );
+    let kind = match args[4] {
+        "place" => {
+            expect_arguments(args, 6, 6)?;
+            EventKind::Place(block_argument(args, 5)?.ok_or("Air cannot be placed")?)
+        }
+        "remove" => {
+            expect_arguments(args, 5, 5)?;
+            EventKind::Remove
+        }
+        "transform" => {
+            expect_arguments(args, 7, 7)?;
+            let from = block_argument(args, 5)?.ok_or("Air cannot be transformed")?;
+            let to = block_argument(args, 6)?.ok_or("Use remove to leave air")?;
+            EventKind::Transform { from, to }
+        }
+        event => return Err(format!("{} is not an event", event)),
+    };
+
+    world
+        .write_resource::<ScheduledEvents>()
+        .schedule(ScheduledEvent { time, cell, kind });
+    Ok(format!(
+        "Scheduled {} at {} {} {} for time {}",
+        args[4], cell.0, cell.1, cell.2, time
+    ))
+}
+
+fn game_mode(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 0, 1)?;
+    if args.is_empty() {
+        return Ok(format!(
+            "The game mode is {}",
+            world.read_resource::<GameMode>().name()
+        ));
+    }
+
+    let mode = GameMode::ALL
+        .iter()
+        .copied()
+        .find(|mode| mode.name().eq_ignore_ascii_case(args[0]))
+        .ok_or_else(|| format!("{} is not a game mode", args[0]))?;
+    *world.write_resource::<GameMode>() = mode;
+    if let Some(mut info) = world.try_fetch_mut::<WorldInfo>() {
+        info.game_mode = mode;
+        info.save();
+    }
+    Ok(format!("Switched to {}", mode.name()))
+}
+
+fn fly(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 0, 0)?;
+    let mode = with_player(world, |player| {
+        player.mode = match player.mode {
+            MovementMode::Flying => MovementMode::Walking,
+            _ => MovementMode::Flying,
+        };
+        player.mode
+    })?;
+    Ok(format!("Now {}", mode.to_string().to_lowercase()))
+}
+
+fn seed(world: &mut World, args: &[&str]) -> Result<String, String> {
+    expect_arguments(args, 0, 1)?;
+    if args.is_empty() {
+        return Ok(format!(
+            "The seed is {}",
+            world.read_resource::<WorldInfo>().seed
+        ));
+    }
+    let seed: u32 = argument(args, 0, "seed")?;
+    regenerate_world(world, seed);
+    Ok(format!("Generated a new world from the seed {}", seed))
+}
+
+/// Replace the terrain with one generated from `seed`. Changes and snapshots
+/// of the old terrain are dropped, and the player is moved to the new spawn.
+fn regenerate_world(world: &mut World, seed: u32) {
+    let blocks: Vec<Entity> = (&world.entities(), &world.read_storage::<Block>())
+        .join()
+        .map(|(entity, _)| entity)
+        .collect();
+    world.delete_entities(&blocks).unwrap();
+
+    spawn_blocks(world, &generate_terrain(seed));
+    *world.write_resource::<BlockHistory>() = BlockHistory::default();
+    *world.write_resource::<ScheduledEvents>() = ScheduledEvents::default();
+    world.write_resource::<UndoStack>().clear();
+    world.write_resource::<Snapshots>().list.clear();
+
+    let spawn = SpawnPoint::find_safe(&world.read_resource::<BlockGrid>());
+    {
+        let mut info = world.write_resource::<WorldInfo>();
+        info.seed = seed;
+        info.spawn = Some(spawn.position.into());
+        info.save();
+    }
+    let _ = with_player(world, |player| player.respawn(spawn.position));
+    world.insert(spawn);
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst::prelude::Builder;
+
+    use super::*;
+
+    /// World with just what the commands use, without a window or assets
+    fn headless_world() -> World {
+        let mut world = World::new();
+        world.register::<Player>();
+        world.register::<Inventory>();
+        world.insert(CommandRegistry::with_builtin_commands());
+        world.insert(WorldTime::default());
+        world.insert(BlockHistory::default());
+        world.insert(BlockGrid::default());
+        world.insert(ScheduledEvents::default());
+        world.insert(GameMode::default());
+        world
+            .create_entity()
+            .with(Player::new(Vector3::new(0., 10., 0.)))
+            .with(Inventory::new())
+            .build();
+        world
+    }
+
+    fn player(world: &World) -> (Vector3<f32>, MovementMode) {
+        let players = world.read_storage::<Player>();
+        let player = (&players).join().next().unwrap();
+        (player.position, player.mode)
+    }
+
+    #[test]
+    fn completes_command_names_and_arguments() {
+        let registry = CommandRegistry::with_builtin_commands();
+        assert_eq!(registry.complete("se"), vec!["seed", "setblock"]);
+        assert_eq!(registry.complete("/ti"), vec!["/time"]);
+        assert_eq!(
+            registry.complete("give gra"),
+            vec!["give grass", "give gravel"]
+        );
+        assert_eq!(
+            registry.complete("setblock 0 0 0 ai"),
+            vec!["setblock 0 0 0 air"]
+        );
+        assert!(registry.complete("tp 1").is_empty());
+    }
+
+    #[test]
+    fn other_modules_can_register_commands() {
+        let mut world = headless_world();
+        world
+            .write_resource::<CommandRegistry>()
+            .register("ping", "", |_, _| Ok("pong".to_string()));
+        assert_eq!(execute_command(&mut world, "/ping"), Ok("pong".to_string()));
+    }
+
+    #[test]
+    fn bad_input_is_explained_with_the_usage() {
+        let mut world = headless_world();
+        assert!(execute_command(&mut world, "").is_err());
+        assert_eq!(
+            execute_command(&mut world, "jump"),
+            Err("Unknown command jump, type help to list them".to_string())
+        );
+        assert_eq!(
+            execute_command(&mut world, "tp 1 2"),
+            Err("Too few arguments\nUsage: tp <x> <y> <z>".to_string())
+        );
+        assert_eq!(
+            execute_command(&mut world, "tp 1 up 3"),
+            Err("up is not a valid y\nUsage: tp <x> <y> <z>".to_string())
+        );
+        assert_eq!(player(&world).0, Vector3::new(0., 10., 0.));
+    }
+
+    #[test]
+    fn teleports_and_toggles_flying() {
+        let mut world = headless_world();
+        execute_command(&mut world, "tp 4 20 -3").unwrap();
+        assert_eq!(player(&world).0, Vector3::new(4., 20., -3.));
+
+        execute_command(&mut world, "fly").unwrap();
+        assert!(player(&world).1 == MovementMode::Flying);
+        execute_command(&mut world, "fly").unwrap();
+        assert!(player(&world).1 == MovementMode::Walking);
+    }
+
+    #[test]
+    fn set_block_is_recorded_at_the_current_time() {
+        let mut world = headless_world();
+        world.write_resource::<WorldTime>().now = 7;
+        execute_command(&mut world, "setblock 1 2 3 dirt").unwrap();
+        execute_command(&mut world, "setblock 1 2 3 air").unwrap();
+
+        let history = world.read_resource::<BlockHistory>();
+        let surfaces: Vec<_> = history
+            .cell((1, 2, 3))
+            .unwrap()
+            .events()
+            .iter()
+            .map(|event| (event.time, event.surface))
+            .collect();
+        assert_eq!(surfaces, vec![(7, Some(BlockSurface::Dirt)), (7, None)]);
+    }
+
+    #[test]
+    fn gives_blocks_to_the_inventory() {
+        let mut world = headless_world();
+        assert_eq!(
+            execute_command(&mut world, "give snow 70"),
+            Ok("Gave 70 snow".to_string())
+        );
+        assert!(execute_command(&mut world, "give air").is_err());
+        assert!(execute_command(&mut world, "give bedrock").is_err());
+
+        let inventories = world.read_storage::<Inventory>();
+        let inventory = (&inventories).join().next().unwrap();
+        let counts: Vec<u32> = inventory
+            .slots
+            .iter()
+            .flatten()
+            .map(|stack| stack.count)
+            .collect();
+        assert_eq!(counts, vec![MAX_STACK_SIZE, 70 - MAX_STACK_SIZE]);
+    }
+
+    #[test]
+    fn switches_the_game_mode() {
+        let mut world = headless_world();
+        assert_eq!(
+            execute_command(&mut world, "gamemode creative"),
+            Ok("Switched to creative".to_string())
+        );
+        assert_eq!(*world.read_resource::<GameMode>(), GameMode::Creative);
+        assert!(execute_command(&mut world, "gamemode hardcore").is_err());
+        assert_eq!(*world.read_resource::<GameMode>(), GameMode::Creative);
+    }
+
+    #[test]
+    fn schedules_events_at_a_time() {
+        let mut world = headless_world();
+        assert_eq!(
+            execute_command(&mut world, "schedule 5 1 2 3 transform snow water"),
+            Ok("Scheduled transform at 1 2 3 for time 5".to_string())
+        );
+        execute_command(&mut world, "schedule 2 1 2 3 place dirt").unwrap();
+        execute_command(&mut world, "schedule 9 1 2 3 remove").unwrap();
+        assert!(execute_command(&mut world, "schedule 9 1 2 3 remove dirt").is_err());
+        assert!(execute_command(&mut world, "schedule 9 1 2 3 place").is_err());
+        assert!(execute_command(&mut world, "schedule 9 1 2 3 transform bedrock air").is_err());
+        assert!(execute_command(&mut world, "schedule 9 1 2 3 grow").is_err());
+
+        let kinds: Vec<_> = world
+            .write_resource::<ScheduledEvents>()
+            .take_due(10)
+            .iter()
+            .map(|event| (event.time, event.cell, event.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (2, (1, 2, 3), EventKind::Place(BlockSurface::Dirt)),
+                (
+                    5,
+                    (1, 2, 3),
+                    EventKind::Transform {
+                        from: BlockSurface::Snow,
+                        to: BlockSurface::Water
+                    }
+                ),
+                (9, (1, 2, 3), EventKind::Remove),
+            ]
+        );
+    }
+
+    #[test]
+    fn time_can_only_go_back() {
+        let mut world = headless_world();
+        world.write_resource::<WorldTime>().now = 10;
+        execute_command(&mut world, "time 4").unwrap();
+        {
+            let world_time = world.read_resource::<WorldTime>();
+            assert_eq!((world_time.now, world_time.offset), (4, -6));
+        }
+        assert!(execute_command(&mut world, "time 11").is_err());
+    }
+}
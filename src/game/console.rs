@@ -0,0 +1,337 @@
+use amethyst::{
+    assets::Loader,
+    controls::HideCursor,
+    core::transform::Parent,
+    derive::SystemDesc,
+    ecs::{Entity, Join, Read, ReadExpect, System, SystemData, Write, WriteExpect, WriteStorage},
+    input::{InputEvent, StringBindings, VirtualKeyCode},
+    prelude::{Builder, WorldExt},
+    renderer::HiddenPropagate,
+    shred::World,
+    shrev::{EventChannel, ReaderId},
+    ui::{
+        Anchor, FontHandle, LineMode, Selectable, Selected, TextEditing, TtfFormat, UiEvent,
+        UiEventType, UiImage, UiText, UiTransform,
+    },
+};
+
+use super::{execute_command, CommandRegistry};
+
+const CONSOLE_WIDTH: f32 = 800.;
+const CONSOLE_HEIGHT: f32 = 300.;
+const INPUT_HEIGHT: f32 = 30.;
+const PADDING: f32 = 8.;
+const MAX_COMMAND_LENGTH: usize = 100;
+const MAX_OUTPUT_LINES: usize = 10;
+const MAX_HISTORY: usize = 50;
+
+const PANEL_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.7];
+const INPUT_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.9];
+const ERROR_COLOR: [f32; 4] = [1.0, 0.5, 0.5, 1.0];
+const OUTPUT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Text box for typing commands, with the latest output above it
+pub struct Console {
+    pub open: bool,
+    output: Vec<String>,
+    failed: bool, // Whether the latest command gave an error, which colors the output
+    history: Vec<String>, // Commands run, oldest first
+    browsing: Option<usize>, // Position in the history while going through it
+    pending: Vec<String>, // Typed commands waiting to be run with the whole world
+    panel: Entity,
+    input: Entity,
+    output_text: Entity,
+}
+
+impl Console {
+    /// Show a message below the earlier ones, dropping the oldest lines
+    pub fn print(&mut self, message: &str) {
+        self.output.extend(message.lines().map(String::from));
+        let extra = self.output.len().saturating_sub(MAX_OUTPUT_LINES);
+        self.output.drain(..extra);
+    }
+}
+
+pub fn initialize_console(world: &mut World) {
+    let font: FontHandle = world.read_resource::<Loader>().load(
+        "Minecraft.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    );
+
+    let panel = world
+        .create_entity()
+        .with(UiTransform::new(
+            "console".to_string(),
+            Anchor::BottomLeft,
+            Anchor::BottomLeft,
+            10.,
+            80.,
+            20.,
+            CONSOLE_WIDTH,
+            CONSOLE_HEIGHT,
+        ))
+        .with(UiImage::SolidColor(PANEL_COLOR))
+        .with(HiddenPropagate::new())
+        .build();
+
+    let output_text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "console output".to_string(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            PADDING,
+            -PADDING,
+            1.,
+            CONSOLE_WIDTH - 2. * PADDING,
+            CONSOLE_HEIGHT - INPUT_HEIGHT - 3. * PADDING,
+        ))
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            OUTPUT_COLOR,
+            18.,
+            LineMode::Wrap,
+            Anchor::BottomLeft,
+        ))
+        .with(Parent { entity: panel })
+        .build();
+
+    let input = world
+        .create_entity()
+        .with(UiTransform::new(
+            "console input".to_string(),
+            Anchor::BottomLeft,
+            Anchor::BottomLeft,
+            PADDING,
+            PADDING,
+            1.,
+            CONSOLE_WIDTH - 2. * PADDING,
+            INPUT_HEIGHT,
+        ))
+        .with(UiImage::SolidColor(INPUT_COLOR))
+        .with(UiText::new(
+            font,
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            20.,
+            LineMode::Single,
+            Anchor::MiddleLeft,
+        ))
+        .with(TextEditing::new(
+            MAX_COMMAND_LENGTH,
+            [0.0, 0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0, 1.0],
+            false,
+        ))
+        .with(Selectable::<()>::new(0))
+        .with(Parent { entity: panel })
+        .build();
+
+    world.insert(Console {
+        open: false,
+        output: vec!["Type help to list the commands".to_string()],
+        failed: false,
+        history: Vec::new(),
+        browsing: None,
+        pending: Vec::new(),
+        panel,
+        input,
+        output_text,
+    });
+}
+
+/// Run the commands typed since the last frame. Commands can change anything,
+/// so they are run by the state with the whole world rather than by a system.
+pub fn run_pending_commands(world: &mut World) {
+    let pending: Vec<String> = world
+        .write_resource::<Console>()
+        .pending
+        .drain(..)
+        .collect();
+    for line in pending {
+        let result = execute_command(world, &line);
+        let mut console = world.write_resource::<Console>();
+        console.print(&format!("> {}", line));
+        match result {
+            Ok(message) => {
+                console.print(&message);
+                console.failed = false;
+            }
+            Err(e) => {
+                console.print(&e);
+                console.failed = true;
+            }
+        }
+    }
+}
+
+/// Replace the typed text, with the text cursor at its end
+fn set_input(
+    texts: &mut WriteStorage<UiText>,
+    editing: &mut WriteStorage<TextEditing>,
+    input: Entity,
+    line: &str,
+) {
+    if let Some(text) = texts.get_mut(input) {
+        text.text = line.to_string();
+    }
+    if let Some(editing) = editing.get_mut(input) {
+        editing.cursor_position = line.chars().count() as isize;
+        editing.highlight_vector = 0;
+    }
+}
+
+/// Opens the console with its key. Enter runs the typed command, Tab completes it,
+/// the arrow keys go through earlier commands and Escape closes the console.
+#[derive(SystemDesc)]
+#[system_desc(name(ConsoleSystemDesc))]
+pub struct ConsoleSystem {
+    #[system_desc(event_channel_reader)]
+    input_reader: ReaderId<InputEvent<StringBindings>>,
+    #[system_desc(event_channel_reader)]
+    ui_reader: ReaderId<UiEvent>,
+}
+
+impl ConsoleSystem {
+    pub fn new(
+        input_reader: ReaderId<InputEvent<StringBindings>>,
+        ui_reader: ReaderId<UiEvent>,
+    ) -> Self {
+        Self {
+            input_reader,
+            ui_reader,
+        }
+    }
+}
+
+impl<'s> System<'s> for ConsoleSystem {
+    type SystemData = (
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, TextEditing>,
+        WriteStorage<'s, HiddenPropagate>,
+        WriteStorage<'s, Selected>,
+        WriteExpect<'s, Console>,
+        ReadExpect<'s, CommandRegistry>,
+        Write<'s, HideCursor>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Read<'s, EventChannel<UiEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut texts,
+            mut editing,
+            mut hidden,
+            mut selected,
+            mut console,
+            registry,
+            mut hide_cursor,
+            input_events,
+            ui_events,
+        ): Self::SystemData,
+    ) {
+        let console = &mut *console;
+
+        // Enter in the selected text box commits it
+        for event in ui_events.read(&mut self.ui_reader) {
+            if event.event_type == UiEventType::ValueCommit && event.target == console.input {
+                let line = texts
+                    .get(console.input)
+                    .map_or("".to_string(), |text| text.text.trim().to_string());
+                if !line.is_empty() {
+                    if console.history.last() != Some(&line) {
+                        console.history.push(line.clone());
+                        let extra = console.history.len().saturating_sub(MAX_HISTORY);
+                        console.history.drain(..extra);
+                    }
+                    console.pending.push(line);
+                }
+                console.browsing = None;
+                set_input(&mut texts, &mut editing, console.input, "");
+            }
+        }
+
+        // Other text boxes take the console key as a character
+        let typing = (&selected).join().next().is_some();
+
+        for event in input_events.read(&mut self.input_reader) {
+            match event {
+                InputEvent::ActionPressed(action) if action == "console" && !typing => {
+                    console.open = true;
+                }
+                InputEvent::KeyPressed { key_code, .. } if console.open => match key_code {
+                    VirtualKeyCode::Tab => {
+                        let line = texts
+                            .get(console.input)
+                            .map_or("".to_string(), |text| text.text.clone());
+                        let completions = registry.complete(&line);
+                        match completions.len() {
+                            0 => {}
+                            1 => {
+                                let line = format!("{} ", completions[0]);
+                                set_input(&mut texts, &mut editing, console.input, &line);
+                            }
+                            _ => console.print(&completions.join("  ")),
+                        }
+                    }
+                    VirtualKeyCode::Up if !console.history.is_empty() => {
+                        let i = console
+                            .browsing
+                            .map_or(console.history.len() - 1, |i| i.saturating_sub(1));
+                        console.browsing = Some(i);
+                        let line = console.history[i].clone();
+                        set_input(&mut texts, &mut editing, console.input, &line);
+                    }
+                    VirtualKeyCode::Down => {
+                        if let Some(i) = console.browsing {
+                            let line = if i + 1 < console.history.len() {
+                                console.browsing = Some(i + 1);
+                                console.history[i + 1].clone()
+                            } else {
+                                console.browsing = None;
+                                "".to_string()
+                            };
+                            set_input(&mut texts, &mut editing, console.input, &line);
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        // Escape captures the cursor again, which also closes the console
+        if console.open && hide_cursor.hide && !hidden.contains(console.panel) {
+            console.open = false;
+        }
+
+        if console.open {
+            if hidden.contains(console.panel) {
+                hide_cursor.hide = false;
+                hidden.remove(console.panel);
+                selected.insert(console.input, Selected).unwrap();
+            }
+        } else if !hidden.contains(console.panel) {
+            selected.remove(console.input);
+            console.browsing = None;
+            set_input(&mut texts, &mut editing, console.input, "");
+            hide_cursor.hide = true;
+            hidden
+                .insert(console.panel, HiddenPropagate::new())
+                .unwrap();
+        }
+
+        if let Some(text) = texts.get_mut(console.output_text) {
+            text.text = console.output.join("\n");
+            text.color = if console.failed {
+                ERROR_COLOR
+            } else {
+                OUTPUT_COLOR
+            };
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use amethyst::{
+    assets::Loader,
+    core::transform::Parent,
+    derive::SystemDesc,
+    ecs::{
+        Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, WriteExpect, WriteStorage,
+    },
+    input::{InputEvent, StringBindings},
+    prelude::{Builder, WorldExt},
+    renderer::HiddenPropagate,
+    shred::World,
+    shrev::{EventChannel, ReaderId},
+    ui::{
+        Anchor, FontHandle, LineMode, Selectable, Selected, TextEditing, TtfFormat, UiEvent,
+        UiEventType, UiImage, UiText, UiTransform,
+    },
+    winit::MouseButton,
+};
+
+use super::inventory_screen::{
+    COLUMNS, HOVERED_SLOT_COLOR, INVENTORY_PANEL_HEIGHT, PADDING, PANEL_COLOR, SLOT_COLOR, SLOT_GAP,
+};
+use super::{
+    block_icon, create_slot_widget, BlockAssets, BlockSurface, DragSource, GameMode, HeldStack,
+    Inventory, InventoryScreen, ItemStack, SlotWidget, SLOT_SIZE,
+};
+
+const SEARCH_HEIGHT: f32 = 30.;
+const SEARCH_LABEL_WIDTH: f32 = 90.;
+const MAX_SEARCH_LENGTH: usize = 20;
+const PANEL_GAP: f32 = 10.; // Space between the palette and the inventory below it
+
+const SEARCH_COLOR: [f32; 4] = [0.05, 0.05, 0.05, 0.9];
+
+/// Every block type for creative play, shown above the inventory screen
+pub struct CreativePalette {
+    panel: Entity,
+    search: Entity,
+    slots: Vec<SlotWidget>,
+    shown: Vec<BlockSurface>, // Block types matching the search, in slot order
+    hovered: Option<usize>,
+}
+
+impl CreativePalette {
+    fn slot_of(&self, entity: Entity) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.background == entity)
+    }
+}
+
+/// Block types whose name contains the search text, ignoring case. Bedrock is left out since
+/// it could never be broken again.
+pub fn search_blocks(query: &str) -> Vec<BlockSurface> {
+    let query = query.trim().to_lowercase();
+    BlockSurface::ALL
+        .iter()
+        .copied()
+        .filter(|surface| surface.is_breakable())
+        .filter(|surface| surface.to_string().to_lowercase().contains(&query))
+        .collect()
+}
+
+pub fn initialize_creative_palette(world: &mut World) {
+    let font: FontHandle = world.read_resource::<Loader>().load(
+        "Minecraft.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    );
+
+    let blocks = search_blocks("");
+    let rows = (blocks.len() + COLUMNS - 1) / COLUMNS;
+    let width = COLUMNS as f32 * (SLOT_SIZE + SLOT_GAP) + 2. * PADDING;
+    let height = SEARCH_HEIGHT + SLOT_GAP + rows as f32 * (SLOT_SIZE + SLOT_GAP) + 2. * PADDING;
+
+    let panel = world
+        .create_entity()
+        .with(UiTransform::new(
+            "creative palette".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            INVENTORY_PANEL_HEIGHT / 2. + PANEL_GAP + height / 2.,
+            10.,
+            width,
+            height,
+        ))
+        .with(UiImage::SolidColor(PANEL_COLOR))
+        .with(HiddenPropagate::new())
+        .build();
+
+    world
+        .create_entity()
+        .with(UiTransform::new(
+            "creative search label".to_string(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            PADDING,
+            -PADDING,
+            1.,
+            SEARCH_LABEL_WIDTH,
+            SEARCH_HEIGHT,
+        ))
+        .with(UiText::new(
+            font.clone(),
+            "Search:".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            20.,
+            LineMode::Single,
+            Anchor::MiddleLeft,
+        ))
+        .with(Parent { entity: panel })
+        .build();
+
+    // Clicking the text box selects it for typing
+    let search = world
+        .create_entity()
+        .with(UiTransform::new(
+            "creative search".to_string(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            PADDING + SEARCH_LABEL_WIDTH,
+            -PADDING,
+            1.,
+            width - 2. * PADDING - SEARCH_LABEL_WIDTH,
+            SEARCH_HEIGHT,
+        ))
+        .with(UiImage::SolidColor(SEARCH_COLOR))
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            20.,
+            LineMode::Single,
+            Anchor::MiddleLeft,
+        ))
+        .with(TextEditing::new(
+            MAX_SEARCH_LENGTH,
+            [0.0, 0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0, 1.0],
+            false,
+        ))
+        .with(Selectable::<()>::new(0))
+        .with(Parent { entity: panel })
+        .build();
+
+    let mut slots = Vec::with_capacity(blocks.len());
+    for i in 0..blocks.len() {
+        let row = i / COLUMNS;
+        let column = i % COLUMNS;
+        let x = PADDING + column as f32 * (SLOT_SIZE + SLOT_GAP) + SLOT_SIZE / 2.;
+        let y = -(PADDING
+            + SEARCH_HEIGHT
+            + SLOT_GAP
+            + row as f32 * (SLOT_SIZE + SLOT_GAP)
+            + SLOT_SIZE / 2.);
+        slots.push(create_slot_widget(
+            world,
+            panel,
+            &format!("palette {i}"),
+            x,
+            y,
+            &font,
+        ));
+    }
+
+    world.insert(CreativePalette {
+        panel,
+        search,
+        slots,
+        shown: blocks,
+        hovered: None,
+    });
+}
+
+/// Shows the palette next to the inventory screen in creative mode.
+///
+/// Left click picks up a full stack of a block type to drop or drag into a slot, and clicking
+/// the palette while holding a stack throws it away. Right click puts a full stack straight
+/// into the selected hotbar slot.
+#[derive(SystemDesc)]
+#[system_desc(name(CreativePaletteSystemDesc))]
+pub struct CreativePaletteSystem {
+    #[system_desc(event_channel_reader)]
+    input_reader: ReaderId<InputEvent<StringBindings>>,
+    #[system_desc(event_channel_reader)]
+    ui_reader: ReaderId<UiEvent>,
+}
+
+impl CreativePaletteSystem {
+    pub fn new(
+        input_reader: ReaderId<InputEvent<StringBindings>>,
+        ui_reader: ReaderId<UiEvent>,
+    ) -> Self {
+        Self {
+            input_reader,
+            ui_reader,
+        }
+    }
+}
+
+impl<'s> System<'s> for CreativePaletteSystem {
+    type SystemData = (
+        WriteStorage<'s, Inventory>,
+        WriteStorage<'s, UiImage>,
+        ReadStorage<'s, UiText>,
+        WriteStorage<'s, HiddenPropagate>,
+        WriteStorage<'s, Selected>,
+        WriteExpect<'s, CreativePalette>,
+        WriteExpect<'s, HeldStack>,
+        ReadExpect<'s, InventoryScreen>,
+        ReadExpect<'s, BlockAssets>,
+        Read<'s, GameMode>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Read<'s, EventChannel<UiEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut inventories,
+            mut images,
+            texts,
+            mut hidden,
+            mut selected,
+            mut palette,
+            mut held,
+            screen,
+            assets,
+            game_mode,
+            input_events,
+            ui_events,
+        ): Self::SystemData,
+    ) {
+        let palette = &mut *palette;
+        let held = &mut *held;
+        let open = screen.open && *game_mode == GameMode::Creative;
+
+        // Track which block type is under the cursor
+        for event in ui_events.read(&mut self.ui_reader) {
+            match event.event_type {
+                UiEventType::HoverStart => {
+                    if let Some(slot) = palette.slot_of(event.target) {
+                        palette.hovered = Some(slot);
+                        images
+                            .insert(event.target, UiImage::SolidColor(HOVERED_SLOT_COLOR))
+                            .unwrap();
+                    }
+                }
+                UiEventType::HoverStop => {
+                    if palette.slot_of(event.target).is_some() {
+                        if palette.hovered == palette.slot_of(event.target) {
+                            palette.hovered = None;
+                        }
+                        images
+                            .insert(event.target, UiImage::SolidColor(SLOT_COLOR))
+                            .unwrap();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for event in input_events.read(&mut self.input_reader) {
+            if !open {
+                continue;
+            }
+            let hovered = match palette.hovered {
+                Some(i) => i,
+                None => continue,
+            };
+            let surface = palette.shown.get(hovered).copied();
+
+            match event {
+                InputEvent::MouseButtonPressed(MouseButton::Left) => {
+                    if held.stack.is_some() {
+                        held.stack = None;
+                    } else if let Some(surface) = surface {
+                        held.stack = Some(ItemStack::full(surface));
+                        held.drag_from = Some(DragSource::Palette);
+                    }
+                }
+                InputEvent::MouseButtonPressed(MouseButton::Right) => {
+                    if let Some(surface) = surface {
+                        for inventory in (&mut inventories).join() {
+                            let selected = inventory.selected;
+                            inventory.slots[selected] = Some(ItemStack::full(surface));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if open {
+            hidden.remove(palette.panel);
+        } else if !hidden.contains(palette.panel) {
+            // Stop typing into the search box once it is hidden
+            selected.remove(palette.search);
+            palette.hovered = None;
+            hidden
+                .insert(palette.panel, HiddenPropagate::new())
+                .unwrap();
+        }
+
+        // Fill the slots with the block types matching the search
+        if open {
+            if let Some(search) = texts.get(palette.search) {
+                palette.shown = search_blocks(&search.text);
+            }
+            for (i, widget) in palette.slots.iter().enumerate() {
+                let stack = palette.shown.get(i).copied().map(ItemStack::full);
+                images
+                    .insert(widget.icon, block_icon(&assets, stack))
+                    .unwrap();
+            }
+        }
+    }
+}
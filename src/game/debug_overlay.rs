@@ -0,0 +1,208 @@
+use amethyst::{
+    assets::Loader,
+    core::{math::Vector3, timing::Time, Transform},
+    ecs::{
+        Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
+    },
+    input::{InputHandler, StringBindings},
+    prelude::{Builder, WorldExt},
+    renderer::{ActiveCamera, Camera},
+    shred::World,
+    ui::{Anchor, FontHandle, LineMode, TtfFormat, UiText, UiTransform},
+    utils::fps_counter::FpsCounter,
+    window::ScreenDimensions,
+};
+
+use super::movement::PHYSICS_TIMESTEP;
+use super::{
+    crosshair_ray, hit_face, raycast_block, Block, BlockGrid, BlockHistory, Player, PlayerCamera,
+    WorldInfo, PLAYER_REACH,
+};
+
+/// Statistics about performance and the world, toggled with F3
+pub struct DebugOverlay {
+    pub shown: bool,
+    text: Entity,
+}
+
+pub fn initialize_debug_overlay(world: &mut World) {
+    let font: FontHandle = world.read_resource::<Loader>().load(
+        "Minecraft.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    );
+
+    let text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "debug overlay".to_string(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            10.,
+            -150.,
+            5.,
+            600.,
+            300.,
+        ))
+        .with(UiText::new(
+            font,
+            "".to_string(),
+            [1.0, 1.0, 0.6, 1.0],
+            20.,
+            LineMode::Wrap,
+            Anchor::TopLeft,
+        ))
+        .build();
+
+    world.insert(DebugOverlay { shown: false, text });
+}
+
+/// Name of an axis direction, like `+X`
+fn direction_name(index: usize, sign: f32) -> String {
+    let axis = ["X", "Y", "Z"][index];
+    if sign < 0. {
+        format!("-{}", axis)
+    } else {
+        format!("+{}", axis)
+    }
+}
+
+#[derive(Default)]
+pub struct DebugOverlaySystem {
+    toggle_held: bool,
+}
+
+impl<'s> System<'s> for DebugOverlaySystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Block>,
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, PlayerCamera>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, UiText>,
+        WriteExpect<'s, DebugOverlay>,
+        Read<'s, ActiveCamera>,
+        ReadExpect<'s, ScreenDimensions>,
+        Read<'s, FpsCounter>,
+        Read<'s, Time>,
+        Read<'s, BlockGrid>,
+        Read<'s, BlockHistory>,
+        ReadExpect<'s, WorldInfo>,
+        Read<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            blocks,
+            players,
+            player_cameras,
+            cameras,
+            locals,
+            mut texts,
+            mut overlay,
+            active_camera,
+            screen_dimensions,
+            fps,
+            time,
+            grid,
+            history,
+            info,
+            input,
+        ): Self::SystemData,
+    ) {
+        let toggle = input.action_is_down("debug_overlay").unwrap_or(false);
+        if toggle && !self.toggle_held {
+            overlay.shown = !overlay.shown;
+        }
+        self.toggle_held = toggle;
+
+        let text = match texts.get_mut(overlay.text) {
+            Some(text) => text,
+            None => return,
+        };
+        if !overlay.shown {
+            text.text = "".to_string();
+            return;
+        }
+
+        let mut lines = vec![
+            format!(
+                "FPS: {:.1} ({:.1} ms)",
+                fps.sampled_fps(),
+                time.delta_real_seconds() * 1000.
+            ),
+            format!("Entities: {}", (&entities).join().count()),
+            format!(
+                "Chunks: {} loaded, {} with history",
+                grid.chunk_count(),
+                history.chunk_count()
+            ),
+        ];
+
+        // Only raycast while shown, as it goes through every block
+        let reach = PLAYER_REACH
+            + (&player_cameras)
+                .join()
+                .map(|camera| camera.distance)
+                .fold(0., f32::max);
+        let mut camera_join = (&cameras, &locals).join();
+        let target = active_camera
+            .entity
+            .and_then(|a| camera_join.get(a, &entities))
+            .or_else(|| camera_join.next())
+            .and_then(|(camera, camera_transform)| {
+                let ray = crosshair_ray(camera, camera_transform, &screen_dimensions);
+                raycast_block(&ray, reach, (&entities, &blocks).join())
+                    .map(|(block, dist, _)| (block, hit_face(&ray, block, dist)))
+            });
+        lines.push(match target {
+            Some((block, (index, sign))) => {
+                let (x, y, z) = block.cell();
+                format!(
+                    "Looking at: {} at {} {} {}, face {}",
+                    block.surface.to_string(),
+                    x,
+                    y,
+                    z,
+                    direction_name(index, sign)
+                )
+            }
+            None => "Looking at: nothing".to_string(),
+        });
+
+        for (player, local) in (&players, &locals).join() {
+            let velocity = (player.position - player.previous_position) / PHYSICS_TIMESTEP;
+            lines.push(format!(
+                "Velocity: {:.2} {:.2} {:.2}, can jump: {}",
+                velocity.x, velocity.y, velocity.z, player.can_jump
+            ));
+
+            // The body only turns around the y axis
+            let forward = local.rotation() * Vector3::new(0., 0., -1.);
+            let index = if forward.x.abs() > forward.z.abs() {
+                0
+            } else {
+                2
+            };
+            let yaw = (-forward.x).atan2(-forward.z).to_degrees();
+            let pitch = (&player_cameras)
+                .join()
+                .map(|camera| camera.pitch.to_degrees())
+                .next()
+                .unwrap_or(0.);
+            lines.push(format!(
+                "Facing: {} (yaw {:.1}, pitch {:.1})",
+                direction_name(index, forward[index]),
+                yaw,
+                pitch
+            ));
+        }
+
+        lines.push(format!("Seed: {}", info.seed));
+        text.text = lines.join("\n");
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use amethyst::{config::Config, utils::application_root_dir};
+use serde::{Deserialize, Serialize};
+
+/// Gameplay settings loaded from `config/game.ron`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub undo_depth: usize, // Most block edits that can be undone
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self { undo_depth: 100 }
+    }
+}
+
+impl GameConfig {
+    /// Load the config file, falling back to the defaults if it is missing or broken
+    pub fn load_or_default() -> Self {
+        let path = application_root_dir()
+            .map(|root| root.join("config/game.ron"))
+            .unwrap_or_else(|_| PathBuf::from("config/game.ron"));
+        Self::load(&path).unwrap_or_else(|e| {
+            log::error!("Could not load {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use std::marker::PhantomData;
+
+use amethyst::{
+    core::{
+        deferred_dispatcher_operation::{AddBundle, AddSystem, AddSystemDesc, DispatcherOperation},
+        ArcThreadPool, SystemBundle, SystemDesc,
+    },
+    ecs::{Dispatcher, DispatcherBuilder, System},
+    prelude::WorldExt,
+    shred::World,
+    DataDispose, DataInit, StateEvent, Trans,
+};
+
+pub type GameTrans<'a, 'b> = Trans<CustomGameData<'a, 'b>, StateEvent>;
+
+/// Systems split by when they run. Core systems (input, UI and rendering) run in every
+/// state, while the systems that play the world only run while it is being played.
+pub struct CustomGameData<'a, 'b> {
+    core: Option<Dispatcher<'a, 'b>>,
+    running: Option<Dispatcher<'a, 'b>>,
+}
+
+impl<'a, 'b> CustomGameData<'a, 'b> {
+    /// Run the systems for one frame, `running` being whether the world is played
+    pub fn update(&mut self, world: &World, running: bool) {
+        if running {
+            if let Some(running) = &mut self.running {
+                running.dispatch(world);
+            }
+        }
+        // Rendering is a core system, so it shows what the world systems did this frame
+        if let Some(core) = &mut self.core {
+            core.dispatch(world);
+        }
+    }
+}
+
+impl DataDispose for CustomGameData<'_, '_> {
+    fn dispose(&mut self, world: &mut World) {
+        if let Some(core) = self.core.take() {
+            core.dispose(world);
+        }
+        if let Some(running) = self.running.take() {
+            running.dispose(world);
+        }
+    }
+}
+
+/// Collects the systems of both dispatchers, which are built once the world exists.
+/// Systems can only depend on systems of the same dispatcher.
+#[derive(Default)]
+pub struct CustomGameDataBuilder<'a, 'b> {
+    core: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
+    running: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
+}
+
+fn names(dependencies: &[&str]) -> Vec<String> {
+    dependencies.iter().map(|name| name.to_string()).collect()
+}
+
+impl<'a, 'b> CustomGameDataBuilder<'a, 'b> {
+    pub fn with_core_bundle<B>(mut self, bundle: B) -> Self
+    where
+        B: SystemBundle<'a, 'b> + 'static,
+    {
+        self.core.push(Box::new(AddBundle { bundle }));
+        self
+    }
+
+    pub fn with_core_system_desc<SD, S>(
+        mut self,
+        system_desc: SD,
+        name: &str,
+        dependencies: &[&str],
+    ) -> Self
+    where
+        SD: SystemDesc<'a, 'b, S> + 'static,
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        self.core.push(Box::new(AddSystemDesc {
+            system_desc,
+            name: name.to_string(),
+            dependencies: names(dependencies),
+            marker: PhantomData::<S>,
+        }));
+        self
+    }
+
+    pub fn with_running_bundle<B>(mut self, bundle: B) -> Self
+    where
+        B: SystemBundle<'a, 'b> + 'static,
+    {
+        self.running.push(Box::new(AddBundle { bundle }));
+        self
+    }
+
+    pub fn with_running<S>(mut self, system: S, name: &str, dependencies: &[&str]) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        self.running.push(Box::new(AddSystem {
+            system,
+            name: name.to_string(),
+            dependencies: names(dependencies),
+        }));
+        self
+    }
+
+    pub fn with_running_system_desc<SD, S>(
+        mut self,
+        system_desc: SD,
+        name: &str,
+        dependencies: &[&str],
+    ) -> Self
+    where
+        SD: SystemDesc<'a, 'b, S> + 'static,
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        self.running.push(Box::new(AddSystemDesc {
+            system_desc,
+            name: name.to_string(),
+            dependencies: names(dependencies),
+            marker: PhantomData::<S>,
+        }));
+        self
+    }
+}
+
+fn build_dispatcher<'a, 'b>(
+    world: &mut World,
+    operations: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
+) -> Dispatcher<'a, 'b> {
+    let mut builder = DispatcherBuilder::new();
+    for operation in operations {
+        operation
+            .exec(world, &mut builder)
+            .unwrap_or_else(|e| panic!("Failed to set up dispatcher: {}", e));
+    }
+
+    let pool = (*world.read_resource::<ArcThreadPool>()).clone();
+    let mut dispatcher = builder.with_pool(pool).build();
+    dispatcher.setup(world);
+    dispatcher
+}
+
+impl<'a, 'b> DataInit<CustomGameData<'a, 'b>> for CustomGameDataBuilder<'a, 'b> {
+    fn build(self, world: &mut World) -> CustomGameData<'a, 'b> {
+        // Core systems first, as they add the event channels the world systems read
+        let core = build_dispatcher(world, self.core);
+        let running = build_dispatcher(world, self.running);
+        CustomGameData {
+            core: Some(core),
+            running: Some(running),
+        }
+    }
+}
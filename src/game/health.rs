@@ -0,0 +1,48 @@
+use amethyst::{
+    ecs::{Join, Read, System, WriteStorage},
+    input::{InputHandler, StringBindings},
+};
+
+use super::{movement::PhysicsConfig, Player, SpawnPoint};
+
+/// Brings dead players back to the spawn point when they press the respawn key
+pub struct RespawnSystem;
+
+impl<'s> System<'s> for RespawnSystem {
+    type SystemData = (
+        WriteStorage<'s, Player>,
+        Read<'s, SpawnPoint>,
+        Read<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(&mut self, (mut players, spawn, input): Self::SystemData) {
+        if !input.action_is_down("respawn").unwrap_or(false) {
+            return;
+        }
+
+        for player in (&mut players).join() {
+            if player.is_dead() {
+                player.respawn(spawn.position);
+            }
+        }
+    }
+}
+
+/// Brings players that fell out of the world back to the spawn point
+pub struct VoidRecoverySystem;
+
+impl<'s> System<'s> for VoidRecoverySystem {
+    type SystemData = (
+        WriteStorage<'s, Player>,
+        Read<'s, SpawnPoint>,
+        Read<'s, PhysicsConfig>,
+    );
+
+    fn run(&mut self, (mut players, spawn, config): Self::SystemData) {
+        for player in (&mut players).join() {
+            if player.position.y < config.void_level {
+                player.teleport(spawn.position);
+            }
+        }
+    }
+}
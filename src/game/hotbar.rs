@@ -0,0 +1,224 @@
+use amethyst::{
+    assets::Loader,
+    derive::SystemDesc,
+    ecs::{
+        Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, WriteExpect, WriteStorage,
+    },
+    input::{InputEvent, ScrollDirection, StringBindings},
+    prelude::{Builder, WorldExt},
+    shred::World,
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, FontHandle, LineMode, Selected, TtfFormat, UiImage, UiText, UiTransform},
+};
+
+use super::{BlockAssets, Inventory, ItemStack, HOTBAR_SIZE};
+
+pub const SLOT_SIZE: f32 = 50.;
+const SLOT_GAP: f32 = 4.;
+const ICON_SIZE: f32 = 36.;
+
+const SLOT_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.6];
+const SELECTED_SLOT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 0.8];
+const EMPTY_ICON: [f32; 4] = [0., 0., 0., 0.];
+
+/// Image showing only the front face of a block texture, which is laid out as an unfolded cube
+pub fn block_icon(assets: &BlockAssets, stack: Option<ItemStack>) -> UiImage {
+    match stack {
+        Some(stack) => UiImage::PartialTexture {
+            tex: assets.texture(stack.surface),
+            left: 1. / 3.,
+            top: 1. / 3.,
+            right: 2. / 3.,
+            bottom: 2. / 3.,
+        },
+        None => UiImage::SolidColor(EMPTY_ICON),
+    }
+}
+
+/// Text shown on top of an icon, single blocks have no count
+pub fn stack_count_text(stack: Option<ItemStack>) -> String {
+    match stack {
+        Some(stack) if stack.count > 1 => stack.count.to_string(),
+        _ => "".to_string(),
+    }
+}
+
+struct HotbarSlot {
+    background: Entity,
+    icon: Entity,
+    count: Entity,
+}
+
+/// UI entities of the hotbar and what they currently show
+pub struct Hotbar {
+    slots: Vec<HotbarSlot>,
+    shown: Vec<Option<ItemStack>>,
+    shown_selected: Option<usize>,
+}
+
+pub fn initialize_hotbar(world: &mut World) {
+    let font: FontHandle = world.read_resource::<Loader>().load(
+        "Minecraft.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    );
+
+    let mut slots = Vec::with_capacity(HOTBAR_SIZE);
+    for i in 0..HOTBAR_SIZE {
+        let x = (i as f32 - (HOTBAR_SIZE / 2) as f32) * (SLOT_SIZE + SLOT_GAP);
+
+        let background = world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("hotbar slot {i}"),
+                Anchor::BottomMiddle,
+                Anchor::BottomMiddle,
+                x,
+                10.,
+                0.,
+                SLOT_SIZE,
+                SLOT_SIZE,
+            ))
+            .with(UiImage::SolidColor(SLOT_COLOR))
+            .build();
+
+        let icon = world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("hotbar icon {i}"),
+                Anchor::BottomMiddle,
+                Anchor::Middle,
+                x,
+                10. + SLOT_SIZE / 2.,
+                1.,
+                ICON_SIZE,
+                ICON_SIZE,
+            ))
+            .with(UiImage::SolidColor(EMPTY_ICON))
+            .build();
+
+        let count = world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("hotbar count {i}"),
+                Anchor::BottomMiddle,
+                Anchor::BottomMiddle,
+                x,
+                10.,
+                2.,
+                SLOT_SIZE - 4.,
+                20.,
+            ))
+            .with(UiText::new(
+                font.clone(),
+                "".to_string(),
+                [1.0, 1.0, 1.0, 1.0],
+                18.,
+                LineMode::Single,
+                Anchor::BottomRight,
+            ))
+            .build();
+
+        slots.push(HotbarSlot {
+            background,
+            icon,
+            count,
+        });
+    }
+
+    world.insert(Hotbar {
+        slots,
+        shown: vec![None; HOTBAR_SIZE],
+        shown_selected: None,
+    });
+}
+
+/// Selects hotbar slots with the number keys and mouse wheel and keeps the hotbar UI up to date
+#[derive(SystemDesc)]
+#[system_desc(name(HotbarSystemDesc))]
+pub struct HotbarSystem {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<InputEvent<StringBindings>>,
+}
+
+impl HotbarSystem {
+    pub fn new(event_reader: ReaderId<InputEvent<StringBindings>>) -> Self {
+        Self { event_reader }
+    }
+}
+
+impl<'s> System<'s> for HotbarSystem {
+    type SystemData = (
+        WriteStorage<'s, Inventory>,
+        WriteStorage<'s, UiImage>,
+        WriteStorage<'s, UiText>,
+        WriteExpect<'s, Hotbar>,
+        ReadExpect<'s, BlockAssets>,
+        ReadStorage<'s, Selected>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+    );
+
+    fn run(
+        &mut self,
+        (mut inventories, mut images, mut texts, mut hotbar, assets, selected, events): Self::SystemData,
+    ) {
+        // Number keys are just typed while a text box is selected
+        let typing = (&selected).join().next().is_some();
+
+        for event in events.read(&mut self.event_reader) {
+            for inventory in (&mut inventories).join() {
+                match event {
+                    // Actions "hotbar_1" to "hotbar_9"
+                    InputEvent::ActionPressed(action) if !typing => {
+                        if let Some(Ok(number)) = action
+                            .strip_prefix("hotbar_")
+                            .map(|number| number.parse::<usize>())
+                        {
+                            if number >= 1 && number <= HOTBAR_SIZE {
+                                inventory.selected = number - 1;
+                            }
+                        }
+                    }
+                    InputEvent::MouseWheelMoved(ScrollDirection::ScrollUp) => {
+                        inventory.selected = (inventory.selected + HOTBAR_SIZE - 1) % HOTBAR_SIZE;
+                    }
+                    InputEvent::MouseWheelMoved(ScrollDirection::ScrollDown) => {
+                        inventory.selected = (inventory.selected + 1) % HOTBAR_SIZE;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let hotbar = &mut *hotbar;
+        for inventory in (&inventories).join() {
+            // Highlight the selected slot
+            if hotbar.shown_selected != Some(inventory.selected) {
+                for (i, slot) in hotbar.slots.iter().enumerate() {
+                    let color = if i == inventory.selected {
+                        SELECTED_SLOT_COLOR
+                    } else {
+                        SLOT_COLOR
+                    };
+                    images.insert(slot.background, UiImage::SolidColor(color)).unwrap();
+                }
+                hotbar.shown_selected = Some(inventory.selected);
+            }
+
+            // Only touch slots whose stack changed
+            for (i, slot) in hotbar.slots.iter().enumerate() {
+                let stack = inventory.slots[i];
+                if hotbar.shown[i] == stack {
+                    continue;
+                }
+
+                images.insert(slot.icon, block_icon(&assets, stack)).unwrap();
+                if let Some(text) = texts.get_mut(slot.count) {
+                    text.text = stack_count_text(stack);
+                }
+                hotbar.shown[i] = stack;
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use amethyst::ecs::{Component, DenseVecStorage};
+use serde::{Deserialize, Serialize};
+
+use super::BlockSurface;
+
+pub const HOTBAR_SIZE: usize = 9;
+pub const INVENTORY_SIZE: usize = 36; // Hotbar followed by the rest of the inventory
+pub const MAX_STACK_SIZE: u32 = 64;
+
+/// Rules for how blocks are gained and used up
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum GameMode {
+    Survival, // Broken blocks are collected and placing a block uses one up
+    Creative, // Any block can be picked and placing is free
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        Self::Survival
+    }
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [Self::Survival, Self::Creative];
+
+    /// Name used in commands
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Survival => "survival",
+            Self::Creative => "creative",
+        }
+    }
+
+    /// The mode after this one, going back to survival after creative
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Survival => Self::Creative,
+            Self::Creative => Self::Survival,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ItemStack {
+    pub surface: BlockSurface,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(surface: BlockSurface, count: u32) -> Self {
+        Self { surface, count }
+    }
+
+    pub fn full(surface: BlockSurface) -> Self {
+        Self::new(surface, MAX_STACK_SIZE)
+    }
+}
+
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    pub selected: usize, // Index of the slot blocks are placed from
+}
+
+impl Component for Inventory {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            slots: vec![None; INVENTORY_SIZE],
+            selected: 0,
+        }
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// Index of the first slot holding the surface
+    pub fn find(&self, surface: BlockSurface) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| slot.map_or(false, |stack| stack.surface == surface))
+    }
+
+    /// Whether one more block of the surface fits
+    pub fn has_room_for(&self, surface: BlockSurface) -> bool {
+        self.slots.iter().any(|slot| match slot {
+            Some(stack) => stack.surface == surface && stack.count < MAX_STACK_SIZE,
+            None => true,
+        })
+    }
+
+    /// Add blocks, filling existing stacks of the same surface first.
+    /// Returns how many did not fit.
+    pub fn add(&mut self, surface: BlockSurface, mut count: u32) -> u32 {
+        for slot in self.slots.iter_mut() {
+            if let Some(stack) = slot {
+                if stack.surface == surface && stack.count < MAX_STACK_SIZE {
+                    let moved = count.min(MAX_STACK_SIZE - stack.count);
+                    stack.count += moved;
+                    count -= moved;
+                }
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if count == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let moved = count.min(MAX_STACK_SIZE);
+                *slot = Some(ItemStack::new(surface, moved));
+                count -= moved;
+            }
+        }
+
+        count
+    }
+
+    /// Take one block from the selected slot, emptying the slot when the last one is taken
+    pub fn take_selected(&mut self) -> Option<BlockSurface> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        stack.count -= 1;
+        let surface = stack.surface;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(surface)
+    }
+
+    /// Take one block of the surface from wherever it is, returns whether there was one
+    pub fn remove_one(&mut self, surface: BlockSurface) -> bool {
+        let i = match self.find(surface) {
+            Some(i) => i,
+            None => return false,
+        };
+        let slot = &mut self.slots[i];
+        if let Some(stack) = slot {
+            stack.count -= 1;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        true
+    }
+}
+
+/// Put the held stack into a slot. Matching stacks are merged as far as they fit,
+/// otherwise the held stack and the slot are swapped.
+pub fn drop_onto(slot: &mut Option<ItemStack>, held: &mut Option<ItemStack>) {
+    let carried = match held.as_mut() {
+        Some(carried) => carried,
+        None => return,
+    };
+
+    match slot.as_mut() {
+        Some(stack) if stack.surface == carried.surface => {
+            let moved = carried.count.min(MAX_STACK_SIZE - stack.count);
+            stack.count += moved;
+            carried.count -= moved;
+            if carried.count == 0 {
+                *held = None;
+            }
+        }
+        _ => std::mem::swap(slot, held),
+    }
+}
+
+/// Put a single block of the held stack into a slot that is empty or holds the same surface
+pub fn drop_one_onto(slot: &mut Option<ItemStack>, held: &mut Option<ItemStack>) {
+    let carried = match held.as_mut() {
+        Some(carried) => carried,
+        None => return,
+    };
+
+    match slot.as_mut() {
+        Some(stack) if stack.surface == carried.surface && stack.count < MAX_STACK_SIZE => {
+            stack.count += 1
+        }
+        None => *slot = Some(ItemStack::new(carried.surface, 1)),
+        _ => return,
+    }
+
+    carried.count -= 1;
+    if carried.count == 0 {
+        *held = None;
+    }
+}
+
+/// Take the larger half of a stack out of a slot
+pub fn take_half(slot: &mut Option<ItemStack>) -> Option<ItemStack> {
+    let stack = slot.as_mut()?;
+    let taken = (stack.count + 1) / 2;
+    stack.count -= taken;
+    let surface = stack.surface;
+    if stack.count == 0 {
+        *slot = None;
+    }
+    Some(ItemStack::new(surface, taken))
+}
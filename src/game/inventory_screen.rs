@@ -0,0 +1,428 @@
+use amethyst::{
+    assets::Loader,
+    controls::HideCursor,
+    core::transform::Parent,
+    derive::SystemDesc,
+    ecs::{
+        Entity, Join, Read, ReadExpect, ReadStorage, System, SystemData, Write, WriteExpect,
+        WriteStorage,
+    },
+    input::{InputEvent, InputHandler, StringBindings},
+    prelude::{Builder, WorldExt},
+    renderer::HiddenPropagate,
+    shred::World,
+    shrev::{EventChannel, ReaderId},
+    ui::{
+        Anchor, FontHandle, Interactable, LineMode, Selected, TtfFormat, UiEvent, UiEventType,
+        UiImage, UiText, UiTransform,
+    },
+    winit::MouseButton,
+};
+
+use super::{
+    block_icon, drop_one_onto, drop_onto, stack_count_text, take_half, BlockAssets, Inventory,
+    ItemStack, HOTBAR_SIZE, INVENTORY_SIZE, SLOT_SIZE,
+};
+
+pub(crate) const COLUMNS: usize = 9;
+pub(crate) const SLOT_GAP: f32 = 4.;
+pub(crate) const PADDING: f32 = 10.;
+const HOTBAR_GAP: f32 = 10.; // Extra space above the hotbar row
+const ICON_SIZE: f32 = 36.;
+
+pub(crate) const PANEL_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 0.9];
+pub(crate) const SLOT_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.8];
+pub(crate) const HOVERED_SLOT_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 0.8];
+
+/// Height of the inventory panel, other screens are laid out around it
+pub(crate) const INVENTORY_PANEL_HEIGHT: f32 =
+    (INVENTORY_SIZE / COLUMNS) as f32 * (SLOT_SIZE + SLOT_GAP) + HOTBAR_GAP + 2. * PADDING;
+
+/// UI entities of one inventory slot
+pub struct SlotWidget {
+    pub background: Entity,
+    pub icon: Entity,
+    pub count: Entity,
+}
+
+/// Create the background, icon and count text of a slot centered at (`x`, `y`) inside `parent`
+pub fn create_slot_widget(
+    world: &mut World,
+    parent: Entity,
+    name: &str,
+    x: f32,
+    y: f32,
+    font: &FontHandle,
+) -> SlotWidget {
+    let background = world
+        .create_entity()
+        .with(UiTransform::new(
+            format!("{name} slot"),
+            Anchor::TopLeft,
+            Anchor::Middle,
+            x,
+            y,
+            1.,
+            SLOT_SIZE,
+            SLOT_SIZE,
+        ))
+        .with(UiImage::SolidColor(SLOT_COLOR))
+        .with(Interactable)
+        .with(Parent { entity: parent })
+        .build();
+
+    let icon = world
+        .create_entity()
+        .with(UiTransform::new(
+            format!("{name} icon"),
+            Anchor::TopLeft,
+            Anchor::Middle,
+            x,
+            y,
+            2.,
+            ICON_SIZE,
+            ICON_SIZE,
+        ))
+        .with(UiImage::SolidColor([0., 0., 0., 0.]))
+        .with(Parent { entity: parent })
+        .build();
+
+    let count = world
+        .create_entity()
+        .with(UiTransform::new(
+            format!("{name} count"),
+            Anchor::TopLeft,
+            Anchor::Middle,
+            x,
+            y,
+            3.,
+            SLOT_SIZE - 4.,
+            SLOT_SIZE - 4.,
+        ))
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            18.,
+            LineMode::Single,
+            Anchor::BottomRight,
+        ))
+        .with(Parent { entity: parent })
+        .build();
+
+    SlotWidget {
+        background,
+        icon,
+        count,
+    }
+}
+
+/// Where a dragged stack was picked up
+#[derive(Clone, Copy, PartialEq)]
+pub enum DragSource {
+    Slot(usize),
+    Palette,
+}
+
+/// Stack carried by the mouse cursor while moving items around
+pub struct HeldStack {
+    pub stack: Option<ItemStack>,
+    pub drag_from: Option<DragSource>, // Set while the button that picked up the stack is down
+    icon: Entity,
+    count: Entity,
+}
+
+/// Grid of all inventory slots, opened with the inventory key
+pub struct InventoryScreen {
+    pub open: bool,
+    panel: Entity,
+    slots: Vec<SlotWidget>, // Same order as Inventory::slots
+    hovered: Option<usize>,
+}
+
+impl InventoryScreen {
+    fn slot_of(&self, entity: Entity) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.background == entity)
+    }
+}
+
+pub fn initialize_inventory_screen(world: &mut World) {
+    let font: FontHandle = world.read_resource::<Loader>().load(
+        "Minecraft.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    );
+
+    let rows = INVENTORY_SIZE / COLUMNS;
+    let width = COLUMNS as f32 * (SLOT_SIZE + SLOT_GAP) + 2. * PADDING;
+
+    let panel = world
+        .create_entity()
+        .with(UiTransform::new(
+            "inventory".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            0.,
+            10.,
+            width,
+            INVENTORY_PANEL_HEIGHT,
+        ))
+        .with(UiImage::SolidColor(PANEL_COLOR))
+        .with(HiddenPropagate::new())
+        .build();
+
+    // The hotbar is the bottom row like on screen, the rest of the inventory is above it
+    let mut slots = Vec::with_capacity(INVENTORY_SIZE);
+    for i in 0..INVENTORY_SIZE {
+        let (row, gap) = if i < HOTBAR_SIZE {
+            (rows - 1, HOTBAR_GAP)
+        } else {
+            ((i - HOTBAR_SIZE) / COLUMNS, 0.)
+        };
+        let column = i % COLUMNS;
+        let x = PADDING + column as f32 * (SLOT_SIZE + SLOT_GAP) + SLOT_SIZE / 2.;
+        let y = -(PADDING + row as f32 * (SLOT_SIZE + SLOT_GAP) + gap + SLOT_SIZE / 2.);
+        slots.push(create_slot_widget(
+            world,
+            panel,
+            &format!("inventory {i}"),
+            x,
+            y,
+            &font,
+        ));
+    }
+
+    // Follows the cursor, drawn above every screen
+    let held_icon = world
+        .create_entity()
+        .with(UiTransform::new(
+            "held icon".to_string(),
+            Anchor::TopLeft,
+            Anchor::Middle,
+            0.,
+            0.,
+            50.,
+            ICON_SIZE,
+            ICON_SIZE,
+        ))
+        .with(UiImage::SolidColor([0., 0., 0., 0.]))
+        .build();
+
+    let held_count = world
+        .create_entity()
+        .with(UiTransform::new(
+            "held count".to_string(),
+            Anchor::TopLeft,
+            Anchor::Middle,
+            0.,
+            0.,
+            51.,
+            SLOT_SIZE - 4.,
+            SLOT_SIZE - 4.,
+        ))
+        .with(UiText::new(
+            font,
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            18.,
+            LineMode::Single,
+            Anchor::BottomRight,
+        ))
+        .build();
+
+    world.insert(InventoryScreen {
+        open: false,
+        panel,
+        slots,
+        hovered: None,
+    });
+    world.insert(HeldStack {
+        stack: None,
+        drag_from: None,
+        icon: held_icon,
+        count: held_count,
+    });
+}
+
+/// Opens and closes the inventory screen and moves stacks between slots with the mouse.
+///
+/// Left click picks up or puts down a whole stack, merging matching stacks, and dragging a
+/// stack to another slot drops it there. Right click picks up half a stack or puts down one block.
+#[derive(SystemDesc)]
+#[system_desc(name(InventoryScreenSystemDesc))]
+pub struct InventoryScreenSystem {
+    #[system_desc(event_channel_reader)]
+    input_reader: ReaderId<InputEvent<StringBindings>>,
+    #[system_desc(event_channel_reader)]
+    ui_reader: ReaderId<UiEvent>,
+}
+
+impl InventoryScreenSystem {
+    pub fn new(
+        input_reader: ReaderId<InputEvent<StringBindings>>,
+        ui_reader: ReaderId<UiEvent>,
+    ) -> Self {
+        Self {
+            input_reader,
+            ui_reader,
+        }
+    }
+}
+
+impl<'s> System<'s> for InventoryScreenSystem {
+    type SystemData = (
+        WriteStorage<'s, Inventory>,
+        WriteStorage<'s, UiImage>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, HiddenPropagate>,
+        ReadStorage<'s, Selected>,
+        WriteExpect<'s, InventoryScreen>,
+        WriteExpect<'s, HeldStack>,
+        Write<'s, HideCursor>,
+        ReadExpect<'s, BlockAssets>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Read<'s, EventChannel<UiEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut inventories,
+            mut images,
+            mut texts,
+            mut ui_transforms,
+            mut hidden,
+            selected,
+            mut screen,
+            mut held,
+            mut hide_cursor,
+            assets,
+            input,
+            input_events,
+            ui_events,
+        ): Self::SystemData,
+    ) {
+        let screen = &mut *screen;
+        let held = &mut *held;
+
+        // Track which slot is under the cursor
+        for event in ui_events.read(&mut self.ui_reader) {
+            match event.event_type {
+                UiEventType::HoverStart => {
+                    if let Some(slot) = screen.slot_of(event.target) {
+                        screen.hovered = Some(slot);
+                        images
+                            .insert(event.target, UiImage::SolidColor(HOVERED_SLOT_COLOR))
+                            .unwrap();
+                    }
+                }
+                UiEventType::HoverStop => {
+                    if screen.slot_of(event.target).is_some() {
+                        if screen.hovered == screen.slot_of(event.target) {
+                            screen.hovered = None;
+                        }
+                        images
+                            .insert(event.target, UiImage::SolidColor(SLOT_COLOR))
+                            .unwrap();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let events: Vec<InputEvent<StringBindings>> =
+            input_events.read(&mut self.input_reader).cloned().collect();
+
+        // The inventory key is just a letter while typing into a text box
+        let typing = (&selected).join().next().is_some();
+
+        for inventory in (&mut inventories).join() {
+            for event in events.iter() {
+                match event {
+                    InputEvent::ActionPressed(action) if action == "inventory" && !typing => {
+                        screen.open = !screen.open;
+                    }
+                    InputEvent::MouseButtonPressed(MouseButton::Left) if screen.open => {
+                        if let Some(i) = screen.hovered {
+                            if held.stack.is_some() {
+                                drop_onto(&mut inventory.slots[i], &mut held.stack);
+                            } else {
+                                held.stack = inventory.slots[i].take();
+                                held.drag_from = held.stack.map(|_| DragSource::Slot(i));
+                            }
+                        }
+                    }
+                    InputEvent::MouseButtonReleased(MouseButton::Left) if screen.open => {
+                        // Releasing over another slot finishes a drag
+                        if let (Some(from), Some(i)) = (held.drag_from.take(), screen.hovered) {
+                            if from != DragSource::Slot(i) {
+                                drop_onto(&mut inventory.slots[i], &mut held.stack);
+                            }
+                        }
+                    }
+                    InputEvent::MouseButtonPressed(MouseButton::Right) if screen.open => {
+                        if let Some(i) = screen.hovered {
+                            if held.stack.is_some() {
+                                drop_one_onto(&mut inventory.slots[i], &mut held.stack);
+                            } else {
+                                held.stack = take_half(&mut inventory.slots[i]);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            // Escape captures the cursor again, which also closes the screen
+            if screen.open && hide_cursor.hide && !hidden.contains(screen.panel) {
+                screen.open = false;
+            }
+
+            if screen.open {
+                hide_cursor.hide = false;
+                hidden.remove(screen.panel);
+            } else if !hidden.contains(screen.panel) {
+                // Put anything still held back into the inventory. What does not fit
+                // stays held, and is shown again when the screen is next opened.
+                if let Some(stack) = held.stack.take() {
+                    let left = inventory.add(stack.surface, stack.count);
+                    if left > 0 {
+                        held.stack = Some(ItemStack::new(stack.surface, left));
+                    }
+                }
+                held.drag_from = None;
+                hide_cursor.hide = true;
+                hidden.insert(screen.panel, HiddenPropagate::new()).unwrap();
+            }
+
+            // Show the current contents of every slot
+            if screen.open {
+                for (widget, stack) in screen.slots.iter().zip(inventory.slots.iter()) {
+                    images.insert(widget.icon, block_icon(&assets, *stack)).unwrap();
+                    if let Some(text) = texts.get_mut(widget.count) {
+                        text.text = stack_count_text(*stack);
+                    }
+                }
+            }
+        }
+
+        // The held stack follows the cursor while the screen is open
+        let shown = held.stack.filter(|_| screen.open);
+        images.insert(held.icon, block_icon(&assets, shown)).unwrap();
+        if let Some(text) = texts.get_mut(held.count) {
+            text.text = stack_count_text(shown);
+        }
+        if let Some((x, y)) = input.mouse_position() {
+            for entity in [held.icon, held.count].iter() {
+                if let Some(transform) = ui_transforms.get_mut(*entity) {
+                    transform.local_x = x;
+                    transform.local_y = -y;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use amethyst::{
+    controls::HideCursor, prelude::WorldExt, shred::World, State, StateData, StateEvent, Trans,
+};
+
+use super::{
+    initialize_settings, CustomGameData, GameMode, GameTrans, InGame, Menu, SettingsMenu, TimeAxis,
+    WorldInfo,
+};
+
+/// Saved worlds listed at once
+const MAX_LISTED_WORLDS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+enum MainMenuAction {
+    NewWorld,
+    GameMode,
+    TimeAxis,
+    Create,
+    LoadWorld,
+    Load(usize), // Index into the listed worlds
+    Settings,
+    Quit,
+    Back,
+}
+
+fn game_mode_label(mode: GameMode) -> String {
+    format!("Game mode: {}", mode.name())
+}
+
+fn time_axis_label(axis: TimeAxis) -> String {
+    format!("Time axis: {}", axis)
+}
+
+/// First screen of the game, which starts a new world or loads a saved one
+#[derive(Default)]
+pub struct MainMenu {
+    menu: Option<Menu<MainMenuAction>>,
+    worlds: Vec<String>,         // Saved worlds, while they are listed
+    creating: Option<WorldInfo>, // New world, while its options are chosen
+}
+
+impl MainMenu {
+    fn show(&mut self, world: &mut World) {
+        if let Some(menu) = self.menu.take() {
+            menu.delete(world);
+        }
+        world.write_resource::<HideCursor>().hide = false;
+
+        let menu = if let Some(info) = &self.creating {
+            let mut menu = Menu::new(world, "New world");
+            menu.add_text(world, &info.name);
+            menu.add_button(
+                world,
+                &game_mode_label(info.game_mode),
+                MainMenuAction::GameMode,
+            );
+            menu.add_button(
+                world,
+                &time_axis_label(info.time_axis),
+                MainMenuAction::TimeAxis,
+            );
+            menu.add_button(world, "Create", MainMenuAction::Create);
+            menu.add_button(world, "Back", MainMenuAction::Back);
+            menu
+        } else if self.worlds.is_empty() {
+            let mut menu = Menu::new(world, "4D Minecraft");
+            menu.add_button(world, "New world", MainMenuAction::NewWorld);
+            menu.add_button(world, "Load world", MainMenuAction::LoadWorld);
+            menu.add_button(world, "Settings", MainMenuAction::Settings);
+            menu.add_button(world, "Quit", MainMenuAction::Quit);
+            menu
+        } else {
+            let mut menu = Menu::new(world, "Load world");
+            for (i, name) in self.worlds.iter().enumerate() {
+                menu.add_button(world, name, MainMenuAction::Load(i));
+            }
+            menu.add_button(world, "Back", MainMenuAction::Back);
+            menu
+        };
+        self.menu = Some(menu);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+}
+
+impl<'a, 'b> State<CustomGameData<'a, 'b>, StateEvent> for MainMenu {
+    fn on_start(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        initialize_settings(data.world);
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.worlds.clear();
+        self.creating = None;
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, CustomGameData<'_, '_>>,
+        event: StateEvent,
+    ) -> GameTrans<'a, 'b> {
+        let action = match &self.menu {
+            Some(menu) => menu.handle_event(data.world, &event),
+            None => None,
+        };
+
+        match action {
+            Some(MainMenuAction::NewWorld) => {
+                self.creating = Some(WorldInfo::new(
+                    &WorldInfo::unused_name(),
+                    GameMode::default(),
+                    TimeAxis::default(),
+                ));
+                self.show(data.world);
+                Trans::None
+            }
+            Some(MainMenuAction::GameMode) => {
+                if let (Some(info), Some(menu)) = (&mut self.creating, &self.menu) {
+                    info.game_mode = info.game_mode.next();
+                    menu.set_label(
+                        data.world,
+                        MainMenuAction::GameMode,
+                        &game_mode_label(info.game_mode),
+                    );
+                }
+                Trans::None
+            }
+            Some(MainMenuAction::TimeAxis) => {
+                if let (Some(info), Some(menu)) = (&mut self.creating, &self.menu) {
+                    info.time_axis = info.time_axis.next();
+                    menu.set_label(
+                        data.world,
+                        MainMenuAction::TimeAxis,
+                        &time_axis_label(info.time_axis),
+                    );
+                }
+                Trans::None
+            }
+            Some(MainMenuAction::Create) => match self.creating.take() {
+                // Saved right away, so playing the world loads the chosen options
+                Some(info) => {
+                    info.save();
+                    Trans::Push(Box::new(InGame::new(&info.name)))
+                }
+                None => Trans::None,
+            },
+            Some(MainMenuAction::LoadWorld) => {
+                let mut worlds = WorldInfo::saved_names();
+                if worlds.is_empty() {
+                    log::info!("There are no saved worlds");
+                    return Trans::None;
+                }
+                worlds.truncate(MAX_LISTED_WORLDS);
+                self.worlds = worlds;
+                self.show(data.world);
+                Trans::None
+            }
+            Some(MainMenuAction::Load(i)) => Trans::Push(Box::new(InGame::new(&self.worlds[i]))),
+            Some(MainMenuAction::Settings) => Trans::Push(Box::new(SettingsMenu::default())),
+            Some(MainMenuAction::Quit) => Trans::Quit,
+            Some(MainMenuAction::Back) => {
+                self.worlds.clear();
+                self.creating = None;
+                self.show(data.world);
+                Trans::None
+            }
+            None => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) -> GameTrans<'a, 'b> {
+        data.data.update(data.world, false);
+        Trans::None
+    }
+}
@@ -0,0 +1,181 @@
+use amethyst::{
+    assets::Loader,
+    ecs::Entity,
+    prelude::{Builder, WorldExt},
+    shred::World,
+    ui::{
+        Anchor, FontHandle, Interactable, LineMode, TtfFormat, UiEvent, UiEventType, UiImage,
+        UiText, UiTransform,
+    },
+    StateEvent,
+};
+
+const BUTTON_WIDTH: f32 = 360.;
+// Small enough for the key bindings to fit below the title in a 720 pixel high window
+const BUTTON_HEIGHT: f32 = 34.;
+const BUTTON_GAP: f32 = 6.;
+const TITLE_Y: f32 = 200.;
+const MENU_Z: f32 = 50.; // In front of the game UI
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const BUTTON_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 0.9];
+const HOVERED_BUTTON_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 0.9];
+
+/// UI entities of one menu screen, a title with a column of buttons below it.
+/// Each button stands for an action of the state showing the menu.
+pub struct Menu<A> {
+    entities: Vec<Entity>,
+    buttons: Vec<(Entity, A)>,
+    font: FontHandle,
+    next_y: f32, // Where the next button goes
+}
+
+impl<A: Copy> Menu<A> {
+    pub fn new(world: &mut World, title: &str) -> Self {
+        let font: FontHandle = world.read_resource::<Loader>().load(
+            "Minecraft.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        // Covers the whole window and darkens the game behind it
+        let background = world
+            .create_entity()
+            .with(
+                UiTransform::new(
+                    "menu background".to_string(),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    0.,
+                    0.,
+                    MENU_Z,
+                    1.,
+                    1.,
+                )
+                .into_percent(),
+            )
+            .with(UiImage::SolidColor(BACKGROUND_COLOR))
+            .build();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "menu title".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                TITLE_Y,
+                MENU_Z + 1.,
+                BUTTON_WIDTH * 2.,
+                60.,
+            ))
+            .with(UiText::new(
+                font.clone(),
+                title.to_string(),
+                [1.0, 1.0, 1.0, 1.0],
+                40.,
+                LineMode::Single,
+                Anchor::Middle,
+            ))
+            .build();
+
+        Self {
+            entities: vec![background, title],
+            buttons: Vec::new(),
+            font,
+            next_y: TITLE_Y - 80.,
+        }
+    }
+
+    fn add_row(&mut self, world: &mut World, text: &str, color: Option<[f32; 4]>) -> Entity {
+        let mut builder = world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("menu row {}", self.entities.len()),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                self.next_y,
+                MENU_Z + 1.,
+                BUTTON_WIDTH,
+                BUTTON_HEIGHT,
+            ))
+            .with(UiText::new(
+                self.font.clone(),
+                text.to_string(),
+                [1.0, 1.0, 1.0, 1.0],
+                24.,
+                LineMode::Single,
+                Anchor::Middle,
+            ));
+        if let Some(color) = color {
+            builder = builder.with(UiImage::SolidColor(color)).with(Interactable);
+        }
+        let entity = builder.build();
+
+        self.entities.push(entity);
+        self.next_y -= BUTTON_HEIGHT + BUTTON_GAP;
+        entity
+    }
+
+    pub fn add_button(&mut self, world: &mut World, label: &str, action: A) -> Entity {
+        let entity = self.add_row(world, label, Some(BUTTON_COLOR));
+        self.buttons.push((entity, action));
+        entity
+    }
+
+    /// Line of text that is not clicked, like a message or a value
+    pub fn add_text(&mut self, world: &mut World, text: &str) -> Entity {
+        self.add_row(world, text, None)
+    }
+
+    /// Highlight buttons under the cursor, and return the action of a clicked one
+    pub fn handle_event(&self, world: &mut World, event: &StateEvent) -> Option<A> {
+        let (event_type, target) = match event {
+            StateEvent::Ui(UiEvent { event_type, target }) => (event_type, *target),
+            _ => return None,
+        };
+        let action = self
+            .buttons
+            .iter()
+            .find(|(entity, _)| *entity == target)
+            .map(|(_, action)| *action)?;
+
+        let mut images = world.write_storage::<UiImage>();
+        match event_type {
+            UiEventType::HoverStart => {
+                images
+                    .insert(target, UiImage::SolidColor(HOVERED_BUTTON_COLOR))
+                    .unwrap();
+                None
+            }
+            UiEventType::HoverStop => {
+                images
+                    .insert(target, UiImage::SolidColor(BUTTON_COLOR))
+                    .unwrap();
+                None
+            }
+            UiEventType::Click => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Change the text of the button for `action`
+    pub fn set_label(&self, world: &mut World, action: A, label: &str)
+    where
+        A: PartialEq,
+    {
+        let mut texts = world.write_storage::<UiText>();
+        for (entity, _) in self.buttons.iter().filter(|(_, a)| *a == action) {
+            if let Some(text) = texts.get_mut(*entity) {
+                text.text = label.to_string();
+            }
+        }
+    }
+
+    /// Remove the menu from the screen
+    pub fn delete(self, world: &mut World) {
+        world.delete_entities(&self.entities).unwrap();
+    }
+}
@@ -1,22 +1,29 @@
 extern crate amethyst;
 use amethyst::{
     assets::AssetLoaderSystemData,
+    config::Config,
     controls::HideCursor,
-    core::Transform,
+    core::{math::Vector3, Transform},
     input::{is_key_down, VirtualKeyCode},
     prelude::*,
     renderer::light::{Light, PointLight},
     renderer::{palette::rgb::Rgb, ImageFormat, Texture},
     ui::{Anchor, UiImage, UiTransform},
     window::ScreenDimensions,
-    SimpleState,
 };
-use noise::{NoiseFn, Perlin};
-use rand::prelude::*;
 
 mod block;
 pub use block::*;
 
+mod terrain;
+pub use terrain::*;
+
+mod world_info;
+pub use world_info::*;
+
+mod block_grid;
+pub use block_grid::*;
+
 pub mod movement;
 
 mod player;
@@ -28,137 +35,182 @@ pub use block_changing::*;
 mod textual_ui;
 pub use textual_ui::*;
 
-pub struct InGame;
+mod spawn;
+pub use spawn::*;
+
+mod health;
+pub use health::*;
+
+mod inventory;
+pub use inventory::*;
+
+mod hotbar;
+pub use hotbar::*;
+
+mod inventory_screen;
+pub use inventory_screen::*;
+
+mod creative_palette;
+pub use creative_palette::*;
+
+mod time_axis;
+pub use time_axis::*;
+
+mod block_history;
+pub use block_history::*;
+
+mod game_config;
+pub use game_config::*;
+
+mod undo;
+pub use undo::*;
+
+mod snapshot;
+pub use snapshot::*;
+
+mod scheduled_events;
+pub use scheduled_events::*;
+
+mod debug_overlay;
+pub use debug_overlay::*;
+
+mod commands;
+pub use commands::*;
+
+mod console;
+pub use console::*;
+
+mod game_data;
+pub use game_data::*;
+
+mod menu;
+pub use menu::*;
+
+mod main_menu;
+pub use main_menu::*;
+
+mod pause;
+pub use pause::*;
 
-impl SimpleState for InGame {
-    fn on_start(&mut self, _data: StateData<'_, GameData<'_, '_>>) {
+mod settings;
+pub use settings::*;
+
+mod settings_menu;
+pub use settings_menu::*;
+
+/// Plays the world with the given name, creating it if it was never saved
+pub struct InGame {
+    world_name: String,
+    // Input made in menus is still queued for the world systems. The cursor is only
+    // captured once they have run, so they ignore it like any input with a free cursor.
+    capture_cursor: bool,
+}
+
+impl InGame {
+    pub fn new(world_name: &str) -> Self {
+        Self {
+            world_name: world_name.to_string(),
+            capture_cursor: true,
+        }
+    }
+}
+
+impl<'a, 'b> State<CustomGameData<'a, 'b>, StateEvent> for InGame {
+    fn on_start(&mut self, _data: StateData<'_, CustomGameData<'_, '_>>) {
         let world = _data.world;
         let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
 
         init_light(world);
-        init_player(world, 0., 9., 0., &dimensions);
-
-        initialize_blocks(world, &{
-            let mut blocks: Vec<Block> = Vec::with_capacity(1_000_000);
-            let perlin = Perlin::new();
-            let map_size = 64.;
-            let chunk_size = 128;
-            let min_height = -10;
-            let max_height = 15.0;
-
-            let mut rng = rand::thread_rng();
-
-            // Random frequency in the range [7, 12)
-            let freq = rng.gen::<f64>() * 5.0 + 3.0;
-
-            // Iterate through x and z values of the map
-            for x in -(chunk_size / 2)..(chunk_size / 2) {
-                for z in -(chunk_size / 2)..(chunk_size / 2) {
-                    let nx = (x as f32 / map_size - 1.0) as f64;
-                    let nz = (z as f32 / map_size - 1.0) as f64;
-
-                    // 3 octaves of Perlin noise
-                    let y = (max_height
-                        * (perlin.get([nx, nz])
-                            + 0.5 * perlin.get([freq * nx, freq * nz])
-                            + 0.25 * perlin.get([2.0 * freq * nx, 2.0 * freq * nz]))
-                        / (1.0 + 0.5 + 0.25))
-                        .round();
-
-                    // Add top layer block
-                    if y > 6.0 {
-                        blocks.push(Block::new(x as f32, y as f32, z as f32, BlockSurface::Snow));
-                    } else if y > -8.0 {
-                        blocks.push(Block::new(
-                            x as f32,
-                            y as f32,
-                            z as f32,
-                            BlockSurface::Grass,
-                        ));
-                    } else if y > -10.0 {
-                        blocks.push(Block::new(
-                            x as f32,
-                            y as f32,
-                            z as f32,
-                            BlockSurface::Gravel,
-                        ));
-                    } else {
-                        // 50 % change of each type of stone
-                        if rand::random() {
-                            blocks.push(Block::new(
-                                x as f32,
-                                y as f32,
-                                z as f32,
-                                BlockSurface::StoneRough,
-                            ));
-                        } else {
-                            blocks.push(Block::new(
-                                x as f32,
-                                y as f32,
-                                z as f32,
-                                BlockSurface::StoneSmooth,
-                            ));
-                        }
-                    }
-
-                    // Add blocks below down to the minimum height.
-                    // The type of block that is added depends on the height.
-                    for i in min_height..y as isize {
-                        if i > -5 {
-                            blocks.push(Block::new(
-                                x as f32,
-                                i as f32,
-                                z as f32,
-                                BlockSurface::Dirt,
-                            ));
-                        } else if i > -8 {
-                            blocks.push(Block::new(
-                                x as f32,
-                                i as f32,
-                                z as f32,
-                                BlockSurface::Gravel,
-                            ));
-                        } else {
-                            // 50 % change of each type of stone
-                            if rand::random() {
-                                blocks.push(Block::new(
-                                    x as f32,
-                                    i as f32,
-                                    z as f32,
-                                    BlockSurface::StoneRough,
-                                ));
-                            } else {
-                                blocks.push(Block::new(
-                                    x as f32,
-                                    i as f32,
-                                    z as f32,
-                                    BlockSurface::StoneSmooth,
-                                ));
-                            }
-                        }
-                    }
-                }
+
+        let mut info = WorldInfo::load_or_create(&self.world_name);
+        initialize_blocks(world, &generate_terrain(info.seed));
+
+        // The spawn point is found once and then kept with the world
+        let spawn = match info.spawn {
+            Some([x, y, z]) => SpawnPoint {
+                position: Vector3::new(x, y, z),
+            },
+            None => {
+                let spawn = SpawnPoint::find_safe(&world.read_resource::<BlockGrid>());
+                info.spawn = Some(spawn.position.into());
+                info.save();
+                spawn
             }
+        };
+        init_player(world, spawn.position.x, spawn.position.y, spawn.position.z, &dimensions);
+        world.insert(spawn);
+        world.insert(info.game_mode);
+        world.insert(info.time_axis);
+        world.insert(WorldTime::default()); // Not carried over from a world played before
 
-            blocks.push(Block::new(0.0, 4.0, 0.0, BlockSurface::Dirt));
-            blocks.push(Block::new(0.0, 1.0, 0.0, BlockSurface::Dirt));
+        // Block changes and the player are saved when leaving the world
+        let history_path = WorldInfo::history_path(&info.name);
+        let history = if history_path.exists() {
+            BlockHistory::load(&history_path).unwrap_or_else(|e| {
+                log::error!("Could not load {}: {}", history_path.display(), e);
+                BlockHistory::default()
+            })
+        } else {
+            BlockHistory::default()
+        };
+        world.insert(history);
+        let events_path = WorldInfo::events_path(&info.name);
+        let events = if events_path.exists() {
+            ScheduledEvents::load(&events_path).unwrap_or_else(|e| {
+                log::error!("Could not load {}: {}", events_path.display(), e);
+                ScheduledEvents::default()
+            })
+        } else {
+            ScheduledEvents::default()
+        };
+        world.insert(events);
+        load_player_state(world, &info.name);
 
-            blocks
-        });
+        world.insert(UndoStack::new(GameConfig::load_or_default().undo_depth));
+        world.insert(info);
+
+        let mut commands = CommandRegistry::with_builtin_commands();
+        register_snapshot_commands(&mut commands);
+        world.insert(commands);
 
         initialize_ui(world);
     }
 
+    /// Save the world and remove all of its entities, so another world can be played
+    fn on_stop(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        save_world(data.world);
+        data.world.delete_all();
+    }
+
+    fn on_resume(&mut self, _data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.capture_cursor = true;
+    }
+
+    fn update(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) -> GameTrans<'a, 'b> {
+        data.data.update(data.world, true);
+        if self.capture_cursor {
+            data.world.write_resource::<HideCursor>().hide = true;
+            self.capture_cursor = false;
+        }
+        run_pending_commands(data.world);
+        Trans::None
+    }
+
     fn handle_event(
         &mut self,
-        data: StateData<'_, GameData<'_, '_>>,
+        data: StateData<'_, CustomGameData<'_, '_>>,
         event: StateEvent,
-    ) -> SimpleTrans {
+    ) -> GameTrans<'a, 'b> {
         let StateData { world, .. } = data;
         if let StateEvent::Window(event) = &event {
             if is_key_down(&event, VirtualKeyCode::Escape) {
+                // Escape first closes whatever freed the cursor, like the inventory
                 let mut hide_cursor = world.write_resource::<HideCursor>();
-                hide_cursor.hide = !hide_cursor.hide;
+                if !hide_cursor.hide {
+                    hide_cursor.hide = true;
+                } else {
+                    return Trans::Push(Box::new(Pause::default()));
+                }
             }
         }
         Trans::None
@@ -202,4 +254,10 @@ pub fn initialize_ui(world: &mut World) {
         .build();
 
     initialize_textual_ui(world);
+    initialize_hotbar(world);
+    initialize_inventory_screen(world);
+    initialize_creative_palette(world);
+    initialize_snapshot_list(world);
+    initialize_debug_overlay(world);
+    initialize_console(world);
 }
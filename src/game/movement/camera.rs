@@ -0,0 +1,113 @@
+use amethyst::{
+    core::{
+        math::{UnitQuaternion, Vector3},
+        transform::Parent,
+        Transform,
+    },
+    ecs::{Entities, Join, Read, ReadStorage, System, SystemData, WriteStorage},
+    input::{InputHandler, StringBindings},
+    renderer::Hidden,
+};
+
+use std::f32::consts::PI;
+
+use crate::game::{BlockGrid, CameraView, PlayerCamera, PlayerModel};
+
+/// How far behind or in front of the eyes the camera is in third person
+pub const THIRD_PERSON_DISTANCE: f32 = 4.0;
+const CAMERA_MARGIN: f32 = 0.2; // Distance kept between the camera and an obstructing block
+const OBSTRUCTION_STEP: f32 = 0.05;
+
+/// Places the camera relative to the player body depending on the view
+#[derive(Default)]
+pub struct CameraSystem {
+    toggle_held: bool,
+}
+
+/// How far the camera can be from `eye` in `direction` before a block is in the way
+fn unobstructed_distance(grid: &BlockGrid, eye: Vector3<f32>, direction: Vector3<f32>) -> f32 {
+    let mut t = 0.;
+    while t < THIRD_PERSON_DISTANCE {
+        let point = eye + direction * t;
+        if grid.is_solid(BlockGrid::cell_at(point.x, point.y, point.z)) {
+            return (t - CAMERA_MARGIN).max(0.);
+        }
+        t += OBSTRUCTION_STEP;
+    }
+    THIRD_PERSON_DISTANCE
+}
+
+impl<'s> System<'s> for CameraSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, PlayerCamera>,
+        ReadStorage<'s, Parent>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, PlayerModel>,
+        WriteStorage<'s, Hidden>,
+        Read<'s, BlockGrid>,
+        Read<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut player_cameras,
+            parents,
+            mut locals,
+            models,
+            mut hidden,
+            grid,
+            input,
+        ): Self::SystemData,
+    ) {
+        let toggle = input.action_is_down("toggle_view").unwrap_or(false);
+        let toggle_pressed = toggle && !self.toggle_held;
+        self.toggle_held = toggle;
+
+        for (entity, camera, parent) in (&entities, &mut player_cameras, &parents).join() {
+            if toggle_pressed {
+                camera.view = camera.view.next();
+            }
+
+            let pitch = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), camera.pitch);
+            // Rotation of the camera and direction from the eyes to the camera, relative to the body
+            let (rotation, direction) = match camera.view {
+                CameraView::FirstPerson => (pitch, Vector3::zeros()),
+                CameraView::ThirdPersonBack => (pitch, pitch * Vector3::z()),
+                CameraView::ThirdPersonFront => (
+                    pitch * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), PI),
+                    pitch * -Vector3::z(),
+                ),
+            };
+
+            // Pull the camera in when terrain is between it and the eyes
+            camera.distance = match (camera.view, locals.get(parent.entity)) {
+                (CameraView::FirstPerson, _) | (_, None) => 0.,
+                (_, Some(body)) => unobstructed_distance(
+                    &grid,
+                    *body.translation(),
+                    body.rotation() * direction,
+                ),
+            };
+
+            if let Some(local) = locals.get_mut(entity) {
+                local.set_rotation(rotation);
+                local.set_translation(direction * camera.distance);
+            }
+        }
+
+        // The player model would block the view in first person
+        let first_person = (&player_cameras)
+            .join()
+            .all(|camera| camera.view == CameraView::FirstPerson);
+        for (entity, _) in (&entities, &models).join() {
+            if first_person {
+                hidden.insert(entity, Hidden).unwrap();
+            } else {
+                hidden.remove(entity);
+            }
+        }
+    }
+}
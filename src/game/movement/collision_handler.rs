@@ -1,4 +1,4 @@
-use crate::game::{BLOCK_SIZE_FROM_CENTER, HEIGHT, PLAYER_SIZE_FROM_CENTER, HEAD_HEIGHT};
+use crate::game::{BLOCK_SIZE_FROM_CENTER, PLAYER_SIZE_FROM_CENTER, HEAD_HEIGHT};
 
 pub struct CollisionHandler {
     pub x_collision: bool,
@@ -7,12 +7,12 @@ pub struct CollisionHandler {
 }
 
 impl CollisionHandler {
-    pub fn new(current: [f32; 3], new_pos: [f32; 3], block: [f32; 3]) -> Self {
+    pub fn new(current: [f32; 3], new_pos: [f32; 3], block: [f32; 3], height: f32) -> Self {
         
         // Current position in block, 1=positive side 2=negative side
         let x1 = current[0] - PLAYER_SIZE_FROM_CENTER < block[0] + BLOCK_SIZE_FROM_CENTER;
         let x2 = current[0] + PLAYER_SIZE_FROM_CENTER > block[0] - BLOCK_SIZE_FROM_CENTER;
-        let y1 = block[1] + BLOCK_SIZE_FROM_CENTER > current[1] - HEIGHT + BLOCK_SIZE_FROM_CENTER; // Touch at feet
+        let y1 = block[1] + BLOCK_SIZE_FROM_CENTER > current[1] - height + BLOCK_SIZE_FROM_CENTER; // Touch at feet
         let y2 = block[1] - BLOCK_SIZE_FROM_CENTER < current[1] + HEAD_HEIGHT; // Touch at top of head (has a height)
         let z1 = current[2] - PLAYER_SIZE_FROM_CENTER < block[2] + BLOCK_SIZE_FROM_CENTER;
         let z2 = current[2] + PLAYER_SIZE_FROM_CENTER > block[2] - BLOCK_SIZE_FROM_CENTER;
@@ -20,7 +20,7 @@ impl CollisionHandler {
         // New position will touch block 1=positive, 2=negative
         let xn1 = new_pos[0] - PLAYER_SIZE_FROM_CENTER < block[0] + BLOCK_SIZE_FROM_CENTER;
         let xn2 = new_pos[0] + PLAYER_SIZE_FROM_CENTER > block[0] - BLOCK_SIZE_FROM_CENTER;
-        let yn1 = block[1] + BLOCK_SIZE_FROM_CENTER > new_pos[1] - HEIGHT + BLOCK_SIZE_FROM_CENTER; // Touch at feet
+        let yn1 = block[1] + BLOCK_SIZE_FROM_CENTER > new_pos[1] - height + BLOCK_SIZE_FROM_CENTER; // Touch at feet
         let yn2 = block[1] - BLOCK_SIZE_FROM_CENTER < new_pos[1] + HEAD_HEIGHT; // Touch at top of head (has a height)
         let zn1 = new_pos[2] - PLAYER_SIZE_FROM_CENTER < block[2] + BLOCK_SIZE_FROM_CENTER;
         let zn2 = new_pos[2] + PLAYER_SIZE_FROM_CENTER > block[2] - BLOCK_SIZE_FROM_CENTER;
@@ -31,4 +31,14 @@ impl CollisionHandler {
             z_collision: x1 && x2 && y1 && y2  && ((zn1 && z2) || (z1 && zn2)), // Touch happens in z
         }
     }
+
+    /// Whether a player with eye height `height` standing at `pos` would be inside the block
+    pub fn intersects(pos: [f32; 3], block: [f32; 3], height: f32) -> bool {
+        pos[0] - PLAYER_SIZE_FROM_CENTER < block[0] + BLOCK_SIZE_FROM_CENTER
+            && pos[0] + PLAYER_SIZE_FROM_CENTER > block[0] - BLOCK_SIZE_FROM_CENTER
+            && block[1] + BLOCK_SIZE_FROM_CENTER > pos[1] - height + BLOCK_SIZE_FROM_CENTER
+            && block[1] - BLOCK_SIZE_FROM_CENTER < pos[1] + HEAD_HEIGHT
+            && pos[2] - PLAYER_SIZE_FROM_CENTER < block[2] + BLOCK_SIZE_FROM_CENTER
+            && pos[2] + PLAYER_SIZE_FROM_CENTER > block[2] - BLOCK_SIZE_FROM_CENTER
+    }
 }
\ No newline at end of file
@@ -1,4 +1,7 @@
+use std::path::PathBuf;
+
 use amethyst::{
+    config::Config,
     core::{SystemBundle, SystemDesc},
     Error,
 };
@@ -9,10 +12,18 @@ pub use movement::*;
 mod rotation;
 pub use rotation::*;
 
+mod camera;
+pub use camera::*;
+
 mod collision_handler;
 pub use collision_handler::*;
 
-pub(crate) struct MovementBundle;
+mod physics_config;
+pub use physics_config::*;
+
+pub(crate) struct MovementBundle {
+    pub physics_config_path: PathBuf,
+}
 
 impl<'a, 'b> SystemBundle<'a, 'b> for MovementBundle {
     fn build(
@@ -20,8 +31,21 @@ impl<'a, 'b> SystemBundle<'a, 'b> for MovementBundle {
         world: &mut amethyst::shred::World,
         dispatcher: &mut amethyst::shred::DispatcherBuilder<'a, 'b>,
     ) -> Result<(), Error> {
-        dispatcher.add(MovementSystem { speed: 5. }, "movement", &["input_system"]);
+        world.insert(PhysicsConfig::load(&self.physics_config_path)?);
+
+        dispatcher.add(
+            PhysicsConfigReloadSystem::new(self.physics_config_path),
+            "physics_config_reload",
+            &[],
+        );
+        // Input is read by the core systems, which are dispatched separately
+        dispatcher.add(
+            MovementSystem::new(),
+            "movement",
+            &["physics_config_reload"],
+        );
         dispatcher.add(RotationSystemDesc::default().build(world), "rotation", &[]);
+        dispatcher.add(CameraSystem::default(), "camera", &["movement", "rotation"]);
 
         Ok(())
     }
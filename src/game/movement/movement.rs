@@ -1,114 +1,424 @@
 use amethyst::{
+    controls::HideCursor,
+    core::math::Vector3,
     core::timing::Time,
     core::transform::Transform,
     derive::SystemDesc,
     ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
     input::{InputHandler, StringBindings},
+    renderer::Camera,
 };
 
-use super::CollisionHandler;
-use crate::game::{Block, Player};
+use super::{CollisionHandler, PhysicsConfig};
+use crate::game::{
+    BlockGrid, MovementMode, Player, PlayerCamera, Settings, BLOCK_SIZE_FROM_CENTER, CROUCH_HEIGHT,
+    HEAD_HEIGHT, HEIGHT, PLAYER_SIZE_FROM_CENTER,
+};
 
 use std::f32::consts::FRAC_1_SQRT_2;
 
-const GRAVITY: f32 = -10.;
-const TERMINAL_VELOCITY: f32 = -50.0;
+const SPRINT_FOV_FACTOR: f32 = 1.15; // How much wider the view gets while sprinting
+const FOV_CHANGE_SPEED: f32 = 8.0; // How fast the FOV approaches its target
+
+const DOUBLE_TAP_TIME: f64 = 0.3; // Longest time between jump presses that toggles flying
+
+/// Length of one physics step in seconds (60 Hz)
+pub const PHYSICS_TIMESTEP: f32 = 1. / 60.;
+/// Most physics steps run in one frame, so a long frame does not stall the game
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// Input held during a frame
+#[derive(Clone, Copy, Default)]
+struct MovementInput {
+    x: Option<f32>,
+    y: Option<f32>,
+    z: Option<f32>,
+    sprint: bool,
+    crouch: bool,
+}
+
 #[derive(SystemDesc)]
 pub struct MovementSystem {
-    pub speed: f32,
+    pub accumulator: f32, // Frame time not yet simulated
+    last_jump: Option<f64>, // Time of the last jump press
+    jump_held: bool,
+    spectator_held: bool,
 }
 
-impl<'s> System<'s> for MovementSystem {
-    type SystemData = (
-        ReadStorage<'s, Block>,
-        WriteStorage<'s, Player>,
-        WriteStorage<'s, Transform>,
-        Read<'s, Time>,
-        Read<'s, InputHandler<StringBindings>>,
-    );
+/// Whether a player with the given eye height at `pos` would be inside any block
+fn is_blocked(grid: &BlockGrid, pos: [f32; 3], height: f32) -> bool {
+    grid.solid_cells_in_box(
+        [
+            pos[0] - PLAYER_SIZE_FROM_CENTER,
+            pos[1] - height + BLOCK_SIZE_FROM_CENTER,
+            pos[2] - PLAYER_SIZE_FROM_CENTER,
+        ],
+        [
+            pos[0] + PLAYER_SIZE_FROM_CENTER,
+            pos[1] + HEAD_HEIGHT,
+            pos[2] + PLAYER_SIZE_FROM_CENTER,
+        ],
+    )
+    .into_iter()
+    .any(|(x, y, z)| CollisionHandler::intersects(pos, [x as f32, y as f32, z as f32], height))
+}
 
-    fn run(&mut self, (blocks, mut players, mut locals, time, input): Self::SystemData) {
-        for (player, local) in (&mut players, &mut locals).join() {
-            // Get key pressed and direction
-            let x_mov = input.axis_value("move_x");
-            let y_mov = input.axis_value("move_y");
-            let z_mov = input.axis_value("move_z");
+/// Whether there is a block right below the feet of a player at `pos`
+fn is_supported(grid: &BlockGrid, pos: [f32; 3], height: f32) -> bool {
+    let feet = pos[1] - height + BLOCK_SIZE_FROM_CENTER;
+    !grid
+        .solid_cells_in_box(
+            [
+                pos[0] - PLAYER_SIZE_FROM_CENTER,
+                feet - 0.1,
+                pos[2] - PLAYER_SIZE_FROM_CENTER,
+            ],
+            [
+                pos[0] + PLAYER_SIZE_FROM_CENTER,
+                feet - 0.1,
+                pos[2] + PLAYER_SIZE_FROM_CENTER,
+            ],
+        )
+        .is_empty()
+}
 
-            let dt = time.delta_seconds();
+impl MovementSystem {
+    pub fn new() -> Self {
+        Self {
+            accumulator: 0.,
+            last_jump: None,
+            jump_held: false,
+            spectator_held: false,
+        }
+    }
 
-            // Normalize movement such that ||Diagonal Movement|| == ||Orthogonal Movement||
-            let dv = if x_mov != Some(0.) && z_mov != Some(0.) {
-                FRAC_1_SQRT_2 * self.speed * dt
-            } else {
-                self.speed * dt
+    /// Switch between walking, flying and spectating on key presses
+    fn update_mode(&mut self, player: &mut Player, jump: bool, spectator: bool, now: f64) {
+        let jump_pressed = jump && !self.jump_held;
+        let spectator_pressed = spectator && !self.spectator_held;
+        self.jump_held = jump;
+        self.spectator_held = spectator;
+
+        if spectator_pressed {
+            player.mode = match player.mode {
+                MovementMode::Spectating => MovementMode::Flying,
+                _ => MovementMode::Spectating,
             };
+        }
 
-            let mut transf = local.clone();
-            let current = local.translation();
+        if jump_pressed {
+            // Double tapping jump toggles flying
+            if self.last_jump.map_or(false, |last| now - last < DOUBLE_TAP_TIME) {
+                player.mode = match player.mode {
+                    MovementMode::Walking => MovementMode::Flying,
+                    _ => MovementMode::Walking,
+                };
+                self.last_jump = None;
+            } else {
+                self.last_jump = Some(now);
+            }
+        }
+
+        if player.mode != MovementMode::Walking {
+            player.y_velocity = 0.;
+        }
+    }
+
+    /// Advance the physics state of the player by one fixed timestep
+    fn step(
+        &self,
+        config: &PhysicsConfig,
+        player: &mut Player,
+        local: &Transform,
+        grid: &BlockGrid,
+        input: MovementInput,
+        dt: f32,
+    ) {
+        // The crouch key moves down while flying
+        let flying = player.mode != MovementMode::Walking;
+        let crouch = input.crouch && !flying;
 
-            // Find matrix of new position
-            transf.append_rotation_x_axis(-player.vert_rotation);
-            if let Some(movement) = x_mov {
-                transf.append_translation_xyz(movement * dv, 0., 0.);
+        // Crouching lowers the eyes while the feet stay put, standing up needs headroom
+        let crouch_drop = HEIGHT - CROUCH_HEIGHT;
+        if crouch && !player.crouching {
+            player.position.y -= crouch_drop;
+            player.crouching = true;
+        } else if !crouch && player.crouching {
+            let mut standing: [f32; 3] = player.position.into();
+            standing[1] += crouch_drop;
+            if !is_blocked(grid, standing, HEIGHT) {
+                player.position.y += crouch_drop;
+                player.crouching = false;
             }
-            if let Some(movement) = z_mov {
-                transf.append_translation_xyz(0., 0., movement * dv);
+        }
+        let height = player.height();
+
+        let moving = input.x.map_or(false, |m| m != 0.) || input.z.map_or(false, |m| m != 0.);
+        player.sprinting = input.sprint && moving && !player.crouching;
+
+        let speed = if player.crouching {
+            config.speed * config.crouch_multiplier
+        } else if player.sprinting {
+            config.speed * config.sprint_multiplier
+        } else {
+            config.speed
+        };
+
+        // Normalize movement such that ||Diagonal Movement|| == ||Orthogonal Movement||
+        let dv = if input.x != Some(0.) && input.z != Some(0.) {
+            FRAC_1_SQRT_2 * speed * dt
+        } else {
+            speed * dt
+        };
+
+        // Physics position with the yaw of the body
+        let mut transf = local.clone();
+        transf.set_translation(player.position);
+        let current = player.position;
+
+        // Find matrix of new position
+        if let Some(movement) = input.x {
+            transf.append_translation_xyz(movement * dv, 0., 0.);
+        }
+        if let Some(movement) = input.z {
+            transf.append_translation_xyz(0., 0., movement * dv);
+        }
+        let mut v_new = 0.0;
+        if flying {
+            // Fly straight up or down without gravity
+            if let Some(movement) = input.y {
+                transf.append_translation_xyz(0., movement * speed * dt, 0.);
             }
-            if let Some(movement) = y_mov {
+        } else {
+            if let Some(movement) = input.y {
                 if player.can_jump && movement > 0. {
-                    player.y_velocity += 5. * movement;
+                    player.y_velocity += config.jump_impulse * movement;
                 }
             }
             // Calculate gravity
             let v = player.y_velocity;
-            let dy = v * dt + GRAVITY * dt * dt; // dy = v dt + g dt^2
-            let mut v_new = (v + GRAVITY * dt).max(TERMINAL_VELOCITY); // v = v0 + g dt
+            let dy = v * dt + config.gravity * dt * dt; // dy = v dt + g dt^2
+            v_new = (v + config.gravity * dt).max(config.terminal_velocity); // v = v0 + g dt
             transf.append_translation_xyz(0.0, dy, 0.0);
-            transf.append_rotation_x_axis(player.vert_rotation);
+        }
 
-            // Find change
-            let mut delta: [f32; 3] = (transf.translation() - local.translation()).into();
-            let transf = transf.translation();
+        // Find change
+        let mut delta: [f32; 3] = (transf.translation() - current).into();
+        let transf = transf.translation();
 
+        // Spectators move through blocks
+        if player.mode == MovementMode::Spectating {
+            player.position += Vector3::from(delta);
             player.can_jump = false;
+            return;
+        }
 
-            // Check collision with blocks
-            for block in (&blocks).join() {
-                let collision = CollisionHandler::new(
-                    [current[0], current[1], current[2]],
-                    [transf[0], transf[1], transf[2]],
-                    block.as_array(),
-                );
-
-                // Update the deltas based on collision in each axis
-                if collision.x_collision {
-                    delta[0] = 0.0
-                }
-                if collision.z_collision {
-                    delta[2] = 0.0
-                }
-                if collision.y_collision {
-                    if delta[1] <= 0. {
-                        // Colliding from top
-                        player.can_jump = true;
-                        delta[1] = 0.0;
-                        v_new = 0.0;
-                    } else {
-                        // Colliding from bottom
-                        delta[1] -= delta[1];
-                        v_new = 0.0;
-                    }
+        let was_grounded = player.can_jump;
+        let fall_speed = -player.y_velocity;
+        player.can_jump = false;
+
+        // Only blocks overlapping the box swept by the player this step
+        let nearby: Vec<[f32; 3]> = grid
+            .solid_cells_in_box(
+                [
+                    current[0].min(transf[0]) - PLAYER_SIZE_FROM_CENTER,
+                    current[1].min(transf[1]) - height + BLOCK_SIZE_FROM_CENTER,
+                    current[2].min(transf[2]) - PLAYER_SIZE_FROM_CENTER,
+                ],
+                [
+                    current[0].max(transf[0]) + PLAYER_SIZE_FROM_CENTER,
+                    current[1].max(transf[1]) + HEAD_HEIGHT,
+                    current[2].max(transf[2]) + PLAYER_SIZE_FROM_CENTER,
+                ],
+            )
+            .into_iter()
+            .map(|(x, y, z)| [x as f32, y as f32, z as f32])
+            .collect();
+
+        // Top of the highest block stopping horizontal movement
+        let mut obstacle_top: Option<f32> = None;
+
+        // Check collision with blocks
+        for block in nearby.iter() {
+            let collision = CollisionHandler::new(
+                [current[0], current[1], current[2]],
+                [transf[0], transf[1], transf[2]],
+                *block,
+                height,
+            );
+
+            // Update the deltas based on collision in each axis
+            if collision.x_collision {
+                delta[0] = 0.0
+            }
+            if collision.z_collision {
+                delta[2] = 0.0
+            }
+            if collision.x_collision || collision.z_collision {
+                let top = block[1] + BLOCK_SIZE_FROM_CENTER;
+                obstacle_top = Some(obstacle_top.map_or(top, |t: f32| t.max(top)));
+            }
+            if collision.y_collision {
+                if delta[1] <= 0. {
+                    // Colliding from top
+                    player.can_jump = true;
+                    delta[1] = 0.0;
+                    v_new = 0.0;
+                } else {
+                    // Colliding from bottom
+                    delta[1] -= delta[1];
+                    v_new = 0.0;
                 }
-                if collision.x_collision && collision.z_collision {
-                    delta[1] = 1.5
+            }
+        }
+
+        // Step up onto a low obstacle if there is headroom above it
+        if let Some(top) = obstacle_top {
+            let feet = current[1] - height + BLOCK_SIZE_FROM_CENTER;
+            let lift = top - feet;
+            if was_grounded && lift > 0. && lift <= config.step_height {
+                let raised = [transf[0], current[1] + lift, transf[2]];
+
+                if !is_blocked(grid, raised, height) {
+                    // Horizontal movement stays blocked until the feet clear the ledge
+                    delta[1] = lift.min(config.step_speed * dt);
+                    v_new = 0.0;
+                    player.can_jump = true;
                 }
             }
+        }
+
+        // Hitting the ground too fast hurts
+        if player.mode == MovementMode::Walking && player.can_jump && !was_grounded {
+            let excess = fall_speed - config.safe_landing_speed;
+            if excess > 0. {
+                player.health = (player.health - excess * config.fall_damage_factor).max(0.);
+            }
+        }
+
+        // Landing while flying down stops flying
+        if player.mode == MovementMode::Flying
+            && player.can_jump
+            && input.y.map_or(false, |m| m < 0.)
+        {
+            player.mode = MovementMode::Walking;
+        }
 
-            // Change position based on deltas
-            local.prepend_translation_x(delta[0]);
-            local.prepend_translation_y(delta[1]);
-            local.prepend_translation_z(delta[2]);
-            player.y_velocity = v_new;
+        // Crouching on the ground never walks off the edge of a block
+        if player.crouching && player.can_jump {
+            let x_moved = [current[0] + delta[0], current[1], current[2]];
+            if !is_supported(grid, x_moved, height) {
+                delta[0] = 0.0;
+            }
+            let z_moved = [current[0] + delta[0], current[1], current[2] + delta[2]];
+            if !is_supported(grid, z_moved, height) {
+                delta[2] = 0.0;
+            }
+        }
+
+        // Change position based on deltas
+        player.position += Vector3::from(delta);
+        player.y_velocity = v_new;
+    }
+}
+
+impl<'s> System<'s> for MovementSystem {
+    type SystemData = (
+        Read<'s, BlockGrid>,
+        Read<'s, PhysicsConfig>,
+        WriteStorage<'s, Player>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, PlayerCamera>,
+        WriteStorage<'s, Camera>,
+        Read<'s, Time>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, HideCursor>,
+        Read<'s, Settings>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            grid,
+            config,
+            mut players,
+            mut locals,
+            player_cameras,
+            mut cameras,
+            time,
+            input,
+            hide_cursor,
+            settings,
+        ): Self::SystemData,
+    ) {
+        // Keys go to the UI while the cursor is shown, e.g. when typing into a text box
+        let controlling = hide_cursor.hide;
+
+        // Ctrl is also held for undo and redo, which should not make the player sprint
+        let shortcut = ["undo", "redo"]
+            .iter()
+            .any(|action| input.action_is_down(action).unwrap_or(false));
+
+        // Get key pressed and direction
+        let movement_input = if controlling {
+            MovementInput {
+                x: input.axis_value("move_x"),
+                y: input.axis_value("move_y"),
+                z: input.axis_value("move_z"),
+                sprint: !shortcut && input.action_is_down("sprint").unwrap_or(false),
+                crouch: input.action_is_down("crouch").unwrap_or(false),
+            }
+        } else {
+            MovementInput::default()
+        };
+        let jump = controlling && input.action_is_down("jump").unwrap_or(false);
+        let spectator = controlling && input.action_is_down("toggle_spectator").unwrap_or(false);
+        let now = time.absolute_time_seconds();
+
+        // Drop time that cannot be caught up on instead of stepping forever
+        self.accumulator = (self.accumulator + time.delta_seconds())
+            .min(PHYSICS_TIMESTEP * MAX_STEPS_PER_FRAME as f32);
+
+        let mut steps = 0;
+        while self.accumulator >= PHYSICS_TIMESTEP {
+            self.accumulator -= PHYSICS_TIMESTEP;
+            steps += 1;
+        }
+
+        // Fraction of a step between the previous and the current physics state
+        let alpha = self.accumulator / PHYSICS_TIMESTEP;
+
+        for (player, local) in (&mut players, &mut locals).join() {
+            // Dead players stay where they fell until they respawn
+            if player.is_dead() {
+                player.previous_position = player.position;
+                continue;
+            }
+
+            self.update_mode(player, jump, spectator, now);
+
+            for _ in 0..steps {
+                player.previous_position = player.position;
+                self.step(&config, player, local, &grid, movement_input, PHYSICS_TIMESTEP);
+            }
+
+            // Render the body in between physics states
+            let previous = player.previous_position;
+            local.set_translation(previous + (player.position - previous) * alpha);
+        }
+
+        // Follow the view settings, widening the view while sprinting
+        let sprinting = (&players).join().any(|player| player.sprinting);
+        let fov = settings.fov.to_radians();
+        for (_, camera) in (&player_cameras, &mut cameras).join() {
+            if let Some(perspective) = camera.projection_mut().as_perspective_mut() {
+                perspective.set_far(settings.view_distance);
+
+                let target = if sprinting { fov * SPRINT_FOV_FACTOR } else { fov };
+                let current = perspective.fovy();
+                let change = (FOV_CHANGE_SPEED * time.delta_seconds()).min(1.);
+                perspective.set_fovy(current + (target - current) * change);
+            }
         }
     }
 }
@@ -0,0 +1,99 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use amethyst::{
+    config::Config,
+    core::timing::Time,
+    ecs::{Read, System, Write},
+};
+use serde::{Deserialize, Serialize};
+
+/// How often the config file is checked for changes, in seconds
+const RELOAD_INTERVAL: f32 = 1.0;
+
+/// Movement tuning loaded from `config/physics.ron`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PhysicsConfig {
+    pub gravity: f32,
+    pub terminal_velocity: f32,
+    pub jump_impulse: f32,
+    pub speed: f32,
+    pub step_height: f32, // Highest obstacle the player walks onto without jumping
+    pub step_speed: f32,  // How fast the player is lifted onto a ledge
+    pub sprint_multiplier: f32,
+    pub crouch_multiplier: f32,
+    pub safe_landing_speed: f32, // Fastest landing without fall damage
+    pub fall_damage_factor: f32, // Health lost per unit of landing speed above the safe speed
+    pub void_level: f32, // Players below this y are brought back to spawn
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            gravity: -10.,
+            terminal_velocity: -50.,
+            jump_impulse: 5.,
+            speed: 5.,
+            step_height: 1.,
+            step_speed: 6.,
+            sprint_multiplier: 1.6,
+            crouch_multiplier: 0.4,
+            safe_landing_speed: 12.,
+            fall_damage_factor: 1.,
+            void_level: -64.,
+        }
+    }
+}
+
+/// Reloads the physics config when its file changes, so movement can be tuned while playing
+pub struct PhysicsConfigReloadSystem {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    since_check: f32,
+}
+
+impl PhysicsConfigReloadSystem {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = Self::modified_time(&path);
+        Self {
+            path,
+            modified,
+            since_check: 0.,
+        }
+    }
+
+    fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+impl<'s> System<'s> for PhysicsConfigReloadSystem {
+    type SystemData = (Write<'s, PhysicsConfig>, Read<'s, Time>);
+
+    fn run(&mut self, (mut config, time): Self::SystemData) {
+        self.since_check += time.delta_real_seconds();
+        if self.since_check < RELOAD_INTERVAL {
+            return;
+        }
+        self.since_check = 0.;
+
+        let modified = Self::modified_time(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+
+        // Keep the old values if the file is broken mid-edit
+        match PhysicsConfig::load(&self.path) {
+            Ok(new_config) => {
+                log::info!("Reloaded {}", self.path.display());
+                *config = new_config;
+            }
+            Err(e) => log::warn!("Could not reload {}: {}", self.path.display(), e),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use amethyst::{
     controls::{HideCursor, WindowFocus},
-    ecs::{Join, Read, System, SystemData, WriteStorage},
+    ecs::{Join, Read, ReadStorage, System, SystemData, WriteStorage},
     shrev::{EventChannel, ReaderId},
     winit::{DeviceEvent, Event},
     SystemDesc,
@@ -10,7 +10,7 @@ use std::f32::consts::FRAC_PI_2;
 
 use derive_new::new as New;
 
-use crate::game::{Player, Transform};
+use crate::game::{Player, PlayerCamera, Settings, Transform};
 
 #[derive(SystemDesc, New)]
 #[system_desc(name(RotationSystemDesc))]
@@ -21,39 +21,47 @@ pub struct RotationSystem {
 
 impl<'a> System<'a> for RotationSystem {
     type SystemData = (
-        WriteStorage<'a, Player>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, PlayerCamera>,
         WriteStorage<'a, Transform>,
         Read<'a, EventChannel<Event>>,
         Read<'a, WindowFocus>,
         Read<'a, HideCursor>,
+        Read<'a, Settings>,
     );
 
-    fn run(&mut self, (mut players, mut transform, events, focus, hide): Self::SystemData) {
-        for (player, local) in (&mut players, &mut transform).join() {
-            for event in events.read(&mut self.reader) {
-                if focus.is_focused && hide.hide {
-                    if let Event::DeviceEvent {
-                        event: DeviceEvent::MouseMotion { delta: (x, y) },
-                        ..
-                    } = *event
-                    {
-                        let theta = player.vert_rotation;
-
-                        let dy = -(y as f32 * 0.1).to_radians();
-                        let dy = if theta + dy < FRAC_PI_2 && theta + dy > -FRAC_PI_2 {
-                            dy
-                        } else {
-                            0.
-                        };
-
-                        let dx = -(x as f32 * 0.1).to_radians();
-
-                        player.vert_rotation += dy;
-                        local.append_rotation_x_axis(dy);
-                        local.prepend_rotation_y_axis(dx);
-                    }
+    fn run(
+        &mut self,
+        (players, mut player_cameras, mut transform, events, focus, hide, settings): Self::SystemData,
+    ) {
+        // Sum up mouse movement since last frame
+        let (mut x, mut y) = (0., 0.);
+        for event in events.read(&mut self.reader) {
+            if focus.is_focused && hide.hide {
+                if let Event::DeviceEvent {
+                    event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                    ..
+                } = *event
+                {
+                    x += dx as f32;
+                    y += dy as f32;
                 }
             }
         }
+
+        // Yaw turns the player body
+        let dx = -(x * settings.mouse_sensitivity).to_radians();
+        for (_, local) in (&players, &mut transform).join() {
+            local.prepend_rotation_y_axis(dx);
+        }
+
+        // Pitch only tilts the camera, which is placed by the camera system
+        let y = if settings.invert_y { -y } else { y };
+        let dy = -(y * settings.mouse_sensitivity).to_radians();
+        for camera in (&mut player_cameras).join() {
+            camera.pitch = (camera.pitch + dy)
+                .max(-FRAC_PI_2 + 0.01)
+                .min(FRAC_PI_2 - 0.01);
+        }
     }
 }
@@ -0,0 +1,87 @@
+use amethyst::{
+    controls::HideCursor,
+    input::{is_key_down, VirtualKeyCode},
+    prelude::WorldExt,
+    State, StateData, StateEvent, Trans,
+};
+
+use super::{CustomGameData, GameTrans, Menu, SettingsMenu};
+
+#[derive(Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Settings,
+    QuitToMenu,
+}
+
+/// Pushed over `InGame` with Escape. The world systems do not run while it is open,
+/// so nothing moves and no blocks can be changed.
+#[derive(Default)]
+pub struct Pause {
+    menu: Option<Menu<PauseAction>>,
+}
+
+impl Pause {
+    fn show(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        data.world.write_resource::<HideCursor>().hide = false;
+
+        let mut menu = Menu::new(data.world, "Paused");
+        menu.add_button(data.world, "Resume", PauseAction::Resume);
+        menu.add_button(data.world, "Settings", PauseAction::Settings);
+        menu.add_button(data.world, "Save and quit to menu", PauseAction::QuitToMenu);
+        self.menu = Some(menu);
+    }
+
+    fn hide(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        if let Some(menu) = self.menu.take() {
+            menu.delete(data.world);
+        }
+    }
+}
+
+impl<'a, 'b> State<CustomGameData<'a, 'b>, StateEvent> for Pause {
+    fn on_start(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.show(data);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.hide(data);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.hide(data);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.show(data);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, CustomGameData<'_, '_>>,
+        event: StateEvent,
+    ) -> GameTrans<'a, 'b> {
+        if let StateEvent::Window(event) = &event {
+            if is_key_down(event, VirtualKeyCode::Escape) {
+                return Trans::Pop;
+            }
+        }
+
+        let action = match &self.menu {
+            Some(menu) => menu.handle_event(data.world, &event),
+            None => None,
+        };
+        match action {
+            Some(PauseAction::Resume) => Trans::Pop,
+            Some(PauseAction::Settings) => Trans::Push(Box::new(SettingsMenu::default())),
+            // The world is saved when `InGame` stops
+            Some(PauseAction::QuitToMenu) => Trans::Sequence(vec![Trans::Pop, Trans::Pop]),
+            None => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) -> GameTrans<'a, 'b> {
+        data.data.update(data.world, false);
+        Trans::None
+    }
+}
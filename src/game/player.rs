@@ -1,31 +1,91 @@
+use std::fmt;
+
 use amethyst::{
-    core::Transform,
-    ecs::{Component, DenseVecStorage},
+    core::{math::Vector3, transform::Parent, Transform},
+    ecs::{Component, DenseVecStorage, NullStorage},
     prelude::*,
-    renderer::{Camera, Material},
-    window::ScreenDimensions, assets::Handle,
+    renderer::{formats::mesh::ObjFormat, Camera, Hidden, MaterialDefaults, Mesh},
+    window::ScreenDimensions, assets::AssetLoaderSystemData,
 };
+use serde::{Deserialize, Serialize};
 
-use super::block::BlockSurface;
+use super::block::{get_mat, BLOCK_SIZE_FROM_CENTER};
+use super::Inventory;
 
 pub const HEIGHT: f32 = 2.0; // From eyes to feet
+pub const CROUCH_HEIGHT: f32 = 1.7; // From eyes to feet while crouching
 pub const HEAD_HEIGHT: f32 = 0.2; // From eyes to top of head
 pub const PLAYER_SIZE_FROM_CENTER: f32 = 0.4;
+pub const MAX_HEALTH: f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum MovementMode {
+    Walking,
+    Flying,     // No gravity, jump and crouch keys move up and down
+    Spectating, // Flying through blocks without collision
+}
+
+impl fmt::Display for MovementMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Walking => "Walking",
+            Self::Flying => "Flying",
+            Self::Spectating => "Spectator",
+        })
+    }
+}
 
 pub struct Player {
+    pub position: Vector3<f32>, // Position of the latest physics step
+    pub previous_position: Vector3<f32>, // Position of the step before, used for interpolation
     pub y_velocity: f32,
+    pub health: f32,
     pub can_jump: bool,
-    pub vert_rotation: f32,
-    pub current_block: Option<(Handle<Material>, BlockSurface)> // Saves material of middle clicked block
+    pub mode: MovementMode,
+    pub sprinting: bool,
+    pub crouching: bool,
 }
 
 impl Player {
-    pub fn new() -> Self {
+    pub fn new(position: Vector3<f32>) -> Self {
         Self {
+            position,
+            previous_position: position,
             y_velocity: 0.,
+            health: MAX_HEALTH,
             can_jump: true,
-            vert_rotation: 0.,
-            current_block: None
+            mode: MovementMode::Walking,
+            sprinting: false,
+            crouching: false,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.
+    }
+
+    /// Move to `position` without passing anything in between
+    pub fn teleport(&mut self, position: Vector3<f32>) {
+        self.position = position;
+        self.previous_position = position;
+        self.y_velocity = 0.;
+    }
+
+    /// Come back to life at `position` with full health
+    pub fn respawn(&mut self, position: Vector3<f32>) {
+        self.teleport(position);
+        self.health = MAX_HEALTH;
+        self.mode = MovementMode::Walking;
+        self.crouching = false;
+        self.sprinting = false;
+    }
+
+    /// Distance from eyes to feet
+    pub fn height(&self) -> f32 {
+        if self.crouching {
+            CROUCH_HEIGHT
+        } else {
+            HEIGHT
         }
     }
 }
@@ -34,6 +94,43 @@ impl Component for Player {
     type Storage = DenseVecStorage<Self>;
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraView {
+    FirstPerson,
+    ThirdPersonBack,
+    ThirdPersonFront, // Looking at the face of the player
+}
+
+impl CameraView {
+    /// View after pressing the toggle key
+    pub fn next(self) -> Self {
+        match self {
+            Self::FirstPerson => Self::ThirdPersonBack,
+            Self::ThirdPersonBack => Self::ThirdPersonFront,
+            Self::ThirdPersonFront => Self::FirstPerson,
+        }
+    }
+}
+
+/// Camera looking out from the player, a child of the player entity
+pub struct PlayerCamera {
+    pub pitch: f32,
+    pub view: CameraView,
+    pub distance: f32, // Current distance from the eyes, shortened when terrain is in the way
+}
+
+impl Component for PlayerCamera {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Marks the model of the player, which is only shown in third person
+#[derive(Default)]
+pub struct PlayerModel;
+
+impl Component for PlayerModel {
+    type Storage = NullStorage<Self>;
+}
+
 pub fn init_player(
     world: &mut World,
     x: f32,
@@ -44,13 +141,52 @@ pub fn init_player(
     let mut transform = Transform::default();
     transform.set_translation_xyz(x, y, z);
 
+    // The body holds position and yaw
+    let body = world
+        .create_entity()
+        .with(transform)
+        .with(Player::new(Vector3::new(x, y, z)))
+        .with(Inventory::new())
+        .build();
+
+    // The camera sits at the eyes of the body and holds pitch
     world
         .create_entity()
         .with(Camera::standard_3d(
             camera_dimensions.width(),
             camera_dimensions.height(),
         ))
-        .with(transform)
-        .with(Player::new())
+        .with(Transform::default())
+        .with(Parent { entity: body })
+        .with(PlayerCamera {
+            pitch: 0.,
+            view: CameraView::FirstPerson,
+            distance: 0.,
+        })
+        .build();
+
+    // Body shaped cube from the feet to the top of the head
+    let mesh = world
+        .exec(|loader: AssetLoaderSystemData<'_, Mesh>| loader.load("mesh/cube.obj", ObjFormat, ()));
+    let mat_default = world.read_resource::<MaterialDefaults>().0.clone();
+    let material = get_mat(world, "player.png", mat_default);
+
+    let feet = -HEIGHT + BLOCK_SIZE_FROM_CENTER;
+    let mut model_transform = Transform::default();
+    model_transform.set_translation_xyz(0., (feet + HEAD_HEIGHT) / 2., 0.);
+    model_transform.set_scale(Vector3::new(
+        2. * PLAYER_SIZE_FROM_CENTER,
+        HEAD_HEIGHT - feet,
+        2. * PLAYER_SIZE_FROM_CENTER,
+    ));
+
+    world
+        .create_entity()
+        .with(model_transform)
+        .with(Parent { entity: body })
+        .with(mesh)
+        .with(material)
+        .with(PlayerModel)
+        .with(Hidden)
         .build();
 }
@@ -0,0 +1,227 @@
+use amethyst::ecs::{Read, System, Write};
+use serde::{Deserialize, Serialize};
+
+use super::{BlockGrid, BlockHistory, BlockSurface, Cell, WorldTime};
+
+/// How much later along the time axis a placed snow block turns into water
+pub const SNOW_MELT_TIME: i32 = 8;
+/// How much later along the time axis uncovered dirt grows grass
+pub const GRASS_GROW_TIME: i32 = 4;
+
+/// What happens to a cell when a scheduled event is reached
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum EventKind {
+    Place(BlockSurface), // A block appears in an empty cell
+    Remove,              // Whatever is in the cell decays
+    Transform {
+        from: BlockSurface,
+        to: BlockSurface,
+    },
+    GrowGrass, // Dirt becomes grass if nothing is on top of it
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ScheduledEvent {
+    pub time: i32,
+    pub cell: Cell,
+    pub kind: EventKind,
+}
+
+/// Events waiting for the world time to reach them, saved with the world
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct ScheduledEvents {
+    pending: Vec<ScheduledEvent>, // Sorted by time
+}
+
+impl ScheduledEvents {
+    pub fn schedule(&mut self, event: ScheduledEvent) {
+        let i = self
+            .pending
+            .partition_point(|other| other.time <= event.time);
+        self.pending.insert(i, event);
+    }
+
+    /// Take out every event at or before `time`, oldest first
+    pub fn take_due(&mut self, time: i32) -> Vec<ScheduledEvent> {
+        let due = self.pending.partition_point(|event| event.time <= time);
+        self.pending.drain(..due).collect()
+    }
+
+    /// Schedule the built-in behaviours that follow `cell` changing to `surface` at `time`
+    pub fn schedule_behaviours(&mut self, time: i32, cell: Cell, surface: Option<BlockSurface>) {
+        let (x, y, z) = cell;
+        match surface {
+            Some(BlockSurface::Snow) => self.schedule(ScheduledEvent {
+                time: time + SNOW_MELT_TIME,
+                cell,
+                kind: EventKind::Transform {
+                    from: BlockSurface::Snow,
+                    to: BlockSurface::Water,
+                },
+            }),
+            Some(BlockSurface::Dirt) => self.schedule(ScheduledEvent {
+                time: time + GRASS_GROW_TIME,
+                cell,
+                kind: EventKind::GrowGrass,
+            }),
+            // Removing a block may uncover dirt below it
+            None => self.schedule(ScheduledEvent {
+                time: time + GRASS_GROW_TIME,
+                cell: (x, y - 1, z),
+                kind: EventKind::GrowGrass,
+            }),
+            _ => {}
+        }
+    }
+}
+
+/// Block in `cell` at `time`. Cells without history have their generated block at all
+/// times, which is what the grid holds for them.
+pub fn block_at(
+    history: &BlockHistory,
+    grid: &BlockGrid,
+    cell: Cell,
+    time: i32,
+) -> Option<BlockSurface> {
+    history
+        .state_at(cell, time)
+        .unwrap_or_else(|| grid.surface(cell))
+}
+
+/// Block an event changes its cell to, or `None` if the event does not apply
+fn outcome(
+    kind: EventKind,
+    current: Option<BlockSurface>,
+    above: Option<BlockSurface>,
+) -> Option<Option<BlockSurface>> {
+    match kind {
+        EventKind::Place(surface) if current.is_none() => Some(Some(surface)),
+        EventKind::Remove if current.map_or(false, |surface| surface.is_breakable()) => Some(None),
+        EventKind::Transform { from, to } if current == Some(from) => Some(Some(to)),
+        EventKind::GrowGrass if current == Some(BlockSurface::Dirt) && above.is_none() => {
+            Some(Some(BlockSurface::Grass))
+        }
+        _ => None,
+    }
+}
+
+/// Applies scheduled events once the world time reaches them. They are recorded in the
+/// block history at their own time, so they hold from then on like any other change.
+pub struct ScheduledEventSystem;
+
+impl<'s> System<'s> for ScheduledEventSystem {
+    type SystemData = (
+        Read<'s, WorldTime>,
+        Write<'s, ScheduledEvents>,
+        Write<'s, BlockHistory>,
+        Read<'s, BlockGrid>,
+    );
+
+    fn run(&mut self, (world_time, mut events, mut history, grid): Self::SystemData) {
+        for event in events.take_due(world_time.now) {
+            let (x, y, z) = event.cell;
+            let current = block_at(&history, &grid, event.cell, event.time);
+            let above = block_at(&history, &grid, (x, y + 1, z), event.time);
+
+            if let Some(surface) = outcome(event.kind, current, above) {
+                history.record(event.time, event.cell, current, surface);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BlockSurface::{Bedrock, Dirt, Grass, Snow, Water};
+
+    fn event(time: i32, kind: EventKind) -> ScheduledEvent {
+        ScheduledEvent {
+            time,
+            cell: (0, 0, 0),
+            kind,
+        }
+    }
+
+    #[test]
+    fn events_only_apply_to_the_blocks_they_expect() {
+        let melt = EventKind::Transform {
+            from: Snow,
+            to: Water,
+        };
+        assert_eq!(outcome(melt, Some(Snow), None), Some(Some(Water)));
+        assert_eq!(outcome(melt, Some(Dirt), None), None);
+
+        assert_eq!(
+            outcome(EventKind::Place(Dirt), None, None),
+            Some(Some(Dirt))
+        );
+        assert_eq!(outcome(EventKind::Place(Dirt), Some(Snow), None), None);
+
+        assert_eq!(outcome(EventKind::Remove, Some(Snow), None), Some(None));
+        assert_eq!(outcome(EventKind::Remove, None, None), None);
+        assert_eq!(outcome(EventKind::Remove, Some(Bedrock), None), None);
+
+        assert_eq!(
+            outcome(EventKind::GrowGrass, Some(Dirt), None),
+            Some(Some(Grass))
+        );
+        assert_eq!(outcome(EventKind::GrowGrass, Some(Dirt), Some(Snow)), None);
+        assert_eq!(outcome(EventKind::GrowGrass, Some(Grass), None), None);
+    }
+
+    #[test]
+    fn due_events_are_taken_oldest_first() {
+        let mut events = ScheduledEvents::default();
+        events.schedule(event(7, EventKind::Remove));
+        events.schedule(event(3, EventKind::Place(Dirt)));
+        events.schedule(event(7, EventKind::GrowGrass));
+        events.schedule(event(12, EventKind::Remove));
+
+        let due: Vec<_> = events
+            .take_due(7)
+            .iter()
+            .map(|event| (event.time, event.kind))
+            .collect();
+        assert_eq!(
+            due,
+            vec![
+                (3, EventKind::Place(Dirt)),
+                (7, EventKind::Remove),
+                (7, EventKind::GrowGrass),
+            ]
+        );
+        assert!(events.take_due(11).is_empty());
+        assert_eq!(events.take_due(12).len(), 1);
+    }
+
+    #[test]
+    fn changes_schedule_their_behaviours() {
+        let mut events = ScheduledEvents::default();
+        events.schedule_behaviours(10, (0, 5, 0), Some(Snow));
+        events.schedule_behaviours(10, (1, 5, 0), Some(Dirt));
+        events.schedule_behaviours(10, (2, 5, 0), None);
+        events.schedule_behaviours(10, (3, 5, 0), Some(Grass));
+
+        let scheduled: Vec<_> = events
+            .take_due(i32::MAX)
+            .iter()
+            .map(|event| (event.time, event.cell, event.kind))
+            .collect();
+        assert_eq!(
+            scheduled,
+            vec![
+                (10 + GRASS_GROW_TIME, (1, 5, 0), EventKind::GrowGrass),
+                (10 + GRASS_GROW_TIME, (2, 4, 0), EventKind::GrowGrass),
+                (
+                    10 + SNOW_MELT_TIME,
+                    (0, 5, 0),
+                    EventKind::Transform {
+                        from: Snow,
+                        to: Water
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use amethyst::{
+    config::Config,
+    input::{Axis, Bindings, Button, InputHandler, StringBindings, VirtualKeyCode},
+    shred::World,
+    utils::application_root_dir,
+    window::Window,
+};
+use serde::{Deserialize, Serialize};
+
+/// Preferences of the player, changed in the settings menu and saved in `settings.ron`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub mouse_sensitivity: f32, // Degrees turned per pixel of mouse movement
+    pub invert_y: bool,
+    pub fov: f32,           // Vertical field of view in degrees, widened while sprinting
+    pub view_distance: f32, // Nothing further from the camera is drawn
+    pub fullscreen: bool,
+    pub key_bindings: BTreeMap<String, VirtualKeyCode>, // Keys of controls changed from `input.ron`
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 0.1,
+            invert_y: false,
+            fov: 60.,
+            view_distance: 256.,
+            fullscreen: false,
+            key_bindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> PathBuf {
+        application_root_dir()
+            .map(|root| root.join("settings.ron"))
+            .unwrap_or_else(|_| PathBuf::from("settings.ron"))
+    }
+
+    /// Load the settings file, falling back to the defaults if there is none yet
+    pub fn load_or_default() -> Self {
+        let path = Self::path();
+        if !path.exists() {
+            return Self::default();
+        }
+        Self::load(&path).unwrap_or_else(|e| {
+            log::error!("Could not load {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let path = Self::path();
+        if let Err(e) = self.write(&path) {
+            log::error!("Could not save {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Something a key can be bound to in the settings menu. Jumping and crouching also
+/// fly up and down, so one control can move several bindings.
+pub struct Control {
+    pub name: &'static str, // Used in the settings file
+    pub label: &'static str,
+    actions: &'static [&'static str],
+    axes: &'static [(&'static str, bool)], // Axis and whether it is the positive side
+}
+
+pub const CONTROLS: [Control; 10] = [
+    Control {
+        name: "forward",
+        label: "Forward",
+        actions: &[],
+        axes: &[("move_z", false)],
+    },
+    Control {
+        name: "backward",
+        label: "Backward",
+        actions: &[],
+        axes: &[("move_z", true)],
+    },
+    Control {
+        name: "left",
+        label: "Left",
+        actions: &[],
+        axes: &[("move_x", false)],
+    },
+    Control {
+        name: "right",
+        label: "Right",
+        actions: &[],
+        axes: &[("move_x", true)],
+    },
+    Control {
+        name: "jump",
+        label: "Jump",
+        actions: &["jump"],
+        axes: &[("move_y", true)],
+    },
+    Control {
+        name: "crouch",
+        label: "Crouch",
+        actions: &["crouch"],
+        axes: &[("move_y", false)],
+    },
+    Control {
+        name: "sprint",
+        label: "Sprint",
+        actions: &["sprint"],
+        axes: &[],
+    },
+    Control {
+        name: "inventory",
+        label: "Inventory",
+        actions: &["inventory"],
+        axes: &[],
+    },
+    Control {
+        name: "console",
+        label: "Console",
+        actions: &["console"],
+        axes: &[],
+    },
+    Control {
+        name: "respawn",
+        label: "Respawn",
+        actions: &["respawn"],
+        axes: &[],
+    },
+];
+
+impl Control {
+    /// Button currently bound to the control
+    pub fn button(&self, bindings: &Bindings<StringBindings>) -> Option<Button> {
+        let action = self
+            .actions
+            .iter()
+            .find_map(|action| action_button(bindings, action));
+        let axis = self
+            .axes
+            .iter()
+            .find_map(|(axis, positive)| match bindings.axis(*axis) {
+                Some(Axis::Emulated { pos, neg }) => Some(if *positive { *pos } else { *neg }),
+                _ => None,
+            });
+        action.or(axis)
+    }
+
+    /// Bind the key to the control instead of its current button
+    pub fn bind(&self, bindings: &mut Bindings<StringBindings>, key: VirtualKeyCode) {
+        for action in self.actions {
+            let combos: Vec<Vec<Button>> = bindings
+                .action_bindings(*action)
+                .map(|combo| combo.to_vec())
+                .collect();
+            for combo in combos {
+                bindings.remove_action_binding(*action, &combo);
+            }
+            if let Err(e) =
+                bindings.insert_action_binding(action.to_string(), vec![Button::Key(key)])
+            {
+                log::error!("Could not bind {:?} to {}: {:?}", key, action, e);
+            }
+        }
+
+        for (axis, positive) in self.axes {
+            let (pos, neg) = match bindings.remove_axis(*axis) {
+                Some(Axis::Emulated { pos, neg }) => (pos, neg),
+                Some(other) => {
+                    log::error!("Cannot bind a key to the axis {}", axis);
+                    let _ = bindings.insert_axis(axis.to_string(), other);
+                    continue;
+                }
+                None => continue,
+            };
+            let (pos, neg) = if *positive {
+                (Button::Key(key), neg)
+            } else {
+                (pos, Button::Key(key))
+            };
+            if let Err(e) = bindings.insert_axis(axis.to_string(), Axis::Emulated { pos, neg }) {
+                log::error!("Could not bind {:?} to {}: {:?}", key, axis, e);
+            }
+        }
+    }
+}
+
+/// Button bound to the action on its own, not as part of a combination like Ctrl+Z
+pub fn action_button(bindings: &Bindings<StringBindings>, action: &str) -> Option<Button> {
+    bindings
+        .action_bindings(action)
+        .find(|combo| combo.len() == 1)
+        .map(|combo| combo[0])
+}
+
+/// Index of the control other than `except` that the key is bound to
+pub fn control_bound_to(
+    bindings: &Bindings<StringBindings>,
+    key: VirtualKeyCode,
+    except: usize,
+) -> Option<usize> {
+    (0..CONTROLS.len())
+        .filter(|i| *i != except)
+        .find(|i| CONTROLS[*i].button(bindings) == Some(Button::Key(key)))
+}
+
+/// Name of a button to show the player, like `W`
+pub fn button_name(button: Option<Button>) -> String {
+    match button {
+        Some(Button::Key(key)) => format!("{:?}", key),
+        Some(button) => format!("{:?}", button),
+        None => "None".to_string(),
+    }
+}
+
+/// Apply the key bindings of the settings on top of those from `input.ron`
+pub fn apply_key_bindings(world: &World) {
+    let settings = world.fetch::<Settings>();
+    let mut input = world.fetch_mut::<InputHandler<StringBindings>>();
+    for control in CONTROLS.iter() {
+        if let Some(key) = settings.key_bindings.get(control.name) {
+            control.bind(&mut input.bindings, *key);
+        }
+    }
+}
+
+pub fn apply_fullscreen(world: &World) {
+    let fullscreen = world.fetch::<Settings>().fullscreen;
+    if let Some(window) = world.try_fetch::<Window>() {
+        let monitor = if fullscreen {
+            Some(window.get_current_monitor())
+        } else {
+            None
+        };
+        window.set_fullscreen(monitor);
+    }
+}
+
+/// Load the settings and apply the ones that are not read every frame
+pub fn initialize_settings(world: &mut World) {
+    world.insert(Settings::load_or_default());
+    apply_key_bindings(world);
+    apply_fullscreen(world);
+}
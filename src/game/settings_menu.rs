@@ -0,0 +1,280 @@
+use amethyst::{
+    ecs::Entity,
+    input::{is_key_down, Button, InputEvent, InputHandler, StringBindings, VirtualKeyCode},
+    prelude::WorldExt,
+    shred::World,
+    ui::UiText,
+    State, StateData, StateEvent, Trans,
+};
+
+use super::{
+    apply_fullscreen, button_name, control_bound_to, CustomGameData, GameTrans, Menu, Settings,
+    CONTROLS,
+};
+
+// Values the settings step through when clicked
+const SENSITIVITIES: [f32; 6] = [0.05, 0.1, 0.15, 0.2, 0.3, 0.4];
+const FOVS: [f32; 6] = [50., 60., 70., 80., 90., 100.];
+const VIEW_DISTANCES: [f32; 5] = [32., 64., 128., 256., 512.];
+
+#[derive(Clone, Copy, PartialEq)]
+enum SettingsAction {
+    Sensitivity,
+    InvertY,
+    Fov,
+    ViewDistance,
+    Fullscreen,
+    KeyBindings,
+    Rebind(usize), // Index into `CONTROLS`
+    Back,
+}
+
+/// The value after `current`, going back to the first after the last
+fn next_value(values: &[f32], current: f32) -> f32 {
+    values
+        .iter()
+        .copied()
+        .find(|value| *value > current + 0.001)
+        .unwrap_or(values[0])
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+/// Options of the game, opened from the main menu or the pause menu. Every change is
+/// saved to the settings file right away and applied to the running game.
+#[derive(Default)]
+pub struct SettingsMenu {
+    menu: Option<Menu<SettingsAction>>,
+    binding_keys: bool, // Whether the key bindings are shown instead of the other options
+    waiting: Option<usize>, // Control waiting for a key press to be bound to it
+    message: Option<Entity>, // Text below the key bindings, telling about conflicting keys
+}
+
+impl SettingsMenu {
+    fn label(&self, world: &World, action: SettingsAction) -> String {
+        let settings = world.read_resource::<Settings>();
+        match action {
+            SettingsAction::Sensitivity => {
+                format!("Mouse sensitivity: {:.2}", settings.mouse_sensitivity)
+            }
+            SettingsAction::InvertY => format!("Invert mouse: {}", on_off(settings.invert_y)),
+            SettingsAction::Fov => format!("Field of view: {}", settings.fov),
+            SettingsAction::ViewDistance => {
+                format!("View distance: {} blocks", settings.view_distance)
+            }
+            SettingsAction::Fullscreen => format!("Fullscreen: {}", on_off(settings.fullscreen)),
+            SettingsAction::KeyBindings => "Key bindings".to_string(),
+            SettingsAction::Rebind(i) if self.waiting == Some(i) => {
+                format!("{}: press a key", CONTROLS[i].label)
+            }
+            SettingsAction::Rebind(i) => {
+                let input = world.read_resource::<InputHandler<StringBindings>>();
+                format!(
+                    "{}: {}",
+                    CONTROLS[i].label,
+                    button_name(CONTROLS[i].button(&input.bindings))
+                )
+            }
+            SettingsAction::Back => "Back".to_string(),
+        }
+    }
+
+    fn show(&mut self, world: &mut World) {
+        if let Some(menu) = self.menu.take() {
+            menu.delete(world);
+        }
+
+        let (title, actions) = if self.binding_keys {
+            let mut actions: Vec<SettingsAction> =
+                (0..CONTROLS.len()).map(SettingsAction::Rebind).collect();
+            actions.push(SettingsAction::Back);
+            ("Key bindings", actions)
+        } else {
+            let actions = vec![
+                SettingsAction::Sensitivity,
+                SettingsAction::InvertY,
+                SettingsAction::Fov,
+                SettingsAction::ViewDistance,
+                SettingsAction::Fullscreen,
+                SettingsAction::KeyBindings,
+                SettingsAction::Back,
+            ];
+            ("Settings", actions)
+        };
+
+        let mut menu = Menu::new(world, title);
+        for action in actions {
+            let label = self.label(world, action);
+            menu.add_button(world, &label, action);
+        }
+        self.message = if self.binding_keys {
+            Some(menu.add_text(world, ""))
+        } else {
+            None
+        };
+        self.menu = Some(menu);
+    }
+
+    /// Show the current value of the setting on its button
+    fn refresh(&self, world: &mut World, action: SettingsAction) {
+        let label = self.label(world, action);
+        if let Some(menu) = &self.menu {
+            menu.set_label(world, action, &label);
+        }
+    }
+
+    fn show_message(&self, world: &mut World, message: &str) {
+        if let Some(entity) = self.message {
+            if let Some(text) = world.write_storage::<UiText>().get_mut(entity) {
+                text.text = message.to_string();
+            }
+        }
+    }
+
+    /// Bind the pressed key to the control, or leave it as it was on Escape.
+    /// A control already using the key gets the key of this control instead.
+    fn bind_key(&mut self, world: &mut World, control: usize, key: VirtualKeyCode) {
+        self.waiting = None;
+        let mut message = String::new();
+        let mut swapped = None;
+        if key != VirtualKeyCode::Escape {
+            let mut settings = world.write_resource::<Settings>();
+            let mut input = world.write_resource::<InputHandler<StringBindings>>();
+            let previous = CONTROLS[control].button(&input.bindings);
+
+            let mut bound = vec![(control, key)];
+            match (control_bound_to(&input.bindings, key, control), previous) {
+                (Some(other), Some(Button::Key(previous))) => {
+                    message = format!(
+                        "{} was on {:?} and is now on {:?}",
+                        CONTROLS[other].label, key, previous
+                    );
+                    bound.push((other, previous));
+                    swapped = Some(other);
+                }
+                (Some(other), _) => {
+                    message = format!("{:?} is already used by {}", key, CONTROLS[other].label);
+                    bound.clear();
+                }
+                (None, _) => {}
+            }
+
+            for (control, key) in bound.iter() {
+                CONTROLS[*control].bind(&mut input.bindings, *key);
+                settings
+                    .key_bindings
+                    .insert(CONTROLS[*control].name.to_string(), *key);
+            }
+            if !bound.is_empty() {
+                settings.save();
+            }
+        }
+
+        self.refresh(world, SettingsAction::Rebind(control));
+        if let Some(other) = swapped {
+            self.refresh(world, SettingsAction::Rebind(other));
+        }
+        self.show_message(world, &message);
+    }
+
+    fn change(&mut self, world: &mut World, action: SettingsAction) {
+        {
+            let mut settings = world.write_resource::<Settings>();
+            match action {
+                SettingsAction::Sensitivity => {
+                    settings.mouse_sensitivity =
+                        next_value(&SENSITIVITIES, settings.mouse_sensitivity);
+                }
+                SettingsAction::InvertY => settings.invert_y = !settings.invert_y,
+                SettingsAction::Fov => settings.fov = next_value(&FOVS, settings.fov),
+                SettingsAction::ViewDistance => {
+                    settings.view_distance = next_value(&VIEW_DISTANCES, settings.view_distance);
+                }
+                SettingsAction::Fullscreen => settings.fullscreen = !settings.fullscreen,
+                _ => return,
+            }
+            settings.save();
+        }
+
+        // The other settings are read by the systems every frame
+        if action == SettingsAction::Fullscreen {
+            apply_fullscreen(world);
+        }
+        self.refresh(world, action);
+    }
+}
+
+impl<'a, 'b> State<CustomGameData<'a, 'b>, StateEvent> for SettingsMenu {
+    fn on_start(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) {
+        if let Some(menu) = self.menu.take() {
+            menu.delete(data.world);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, CustomGameData<'_, '_>>,
+        event: StateEvent,
+    ) -> GameTrans<'a, 'b> {
+        let world = data.world;
+
+        // The next key pressed is bound, including the ones used by the menu
+        if let Some(control) = self.waiting {
+            if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = event {
+                self.bind_key(world, control, key_code);
+            }
+            return Trans::None;
+        }
+
+        let back = match &event {
+            StateEvent::Window(event) => is_key_down(event, VirtualKeyCode::Escape),
+            _ => false,
+        };
+        let action = match &self.menu {
+            Some(menu) => menu.handle_event(world, &event),
+            None => None,
+        };
+
+        match action {
+            _ if back || action == Some(SettingsAction::Back) => {
+                if self.binding_keys {
+                    self.binding_keys = false;
+                    self.show(world);
+                    Trans::None
+                } else {
+                    Trans::Pop
+                }
+            }
+            Some(SettingsAction::KeyBindings) => {
+                self.binding_keys = true;
+                self.show(world);
+                Trans::None
+            }
+            Some(SettingsAction::Rebind(control)) => {
+                self.waiting = Some(control);
+                self.refresh(world, SettingsAction::Rebind(control));
+                Trans::None
+            }
+            Some(action) => {
+                self.change(world, action);
+                Trans::None
+            }
+            None => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: StateData<'_, CustomGameData<'_, '_>>) -> GameTrans<'a, 'b> {
+        data.data.update(data.world, false);
+        Trans::None
+    }
+}
@@ -0,0 +1,458 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use amethyst::{
+    assets::Loader,
+    config::Config,
+    controls::HideCursor,
+    derive::SystemDesc,
+    ecs::{Entity, Join, Read, ReadExpect, System, SystemData, Write, WriteExpect, WriteStorage},
+    input::{InputEvent, StringBindings},
+    prelude::{Builder, WorldExt},
+    shred::World,
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, FontHandle, LineMode, TtfFormat, UiText, UiTransform},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    BlockHistory, CommandRegistry, Inventory, ItemStack, MovementMode, Player, ScheduledEvents,
+    UndoStack, WorldInfo, INVENTORY_SIZE,
+};
+
+/// Everything about the player that is kept in snapshots and saved with a world
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PlayerState {
+    pub position: [f32; 3],
+    pub y_velocity: f32,
+    pub health: f32,
+    pub mode: MovementMode,
+    pub slots: Vec<Option<ItemStack>>,
+    pub selected: usize,
+}
+
+impl PlayerState {
+    pub fn capture(player: &Player, inventory: &Inventory) -> Self {
+        Self {
+            position: player.position.into(),
+            y_velocity: player.y_velocity,
+            health: player.health,
+            mode: player.mode,
+            slots: inventory.slots.clone(),
+            selected: inventory.selected,
+        }
+    }
+
+    pub fn apply(&self, player: &mut Player, inventory: &mut Inventory) {
+        player.teleport(self.position.into());
+        player.y_velocity = self.y_velocity;
+        player.health = self.health;
+        player.mode = self.mode;
+
+        // Saved inventories of another size are cut off or padded with empty slots
+        inventory.slots = self.slots.clone();
+        inventory.slots.resize(INVENTORY_SIZE, None);
+        inventory.selected = self.selected.min(INVENTORY_SIZE - 1);
+    }
+}
+
+/// Put the player where they were when the world was last left, if it was saved
+pub fn load_player_state(world: &mut World, name: &str) {
+    let path = WorldInfo::player_path(name);
+    if !path.exists() {
+        return;
+    }
+    let state = match PlayerState::load(&path) {
+        Ok(state) => state,
+        Err(e) => {
+            log::error!("Could not load {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let mut players = world.write_storage::<Player>();
+    let mut inventories = world.write_storage::<Inventory>();
+    for (player, inventory) in (&mut players, &mut inventories).join() {
+        state.apply(player, inventory);
+    }
+}
+
+/// Save the block history, scheduled events and the player of the world being played
+pub fn save_world(world: &World) {
+    let info = world.read_resource::<WorldInfo>();
+    let history_path = WorldInfo::history_path(&info.name);
+    if let Err(e) = world.read_resource::<BlockHistory>().save(&history_path) {
+        log::error!("Could not save {}: {}", history_path.display(), e);
+    }
+
+    let events_path = WorldInfo::events_path(&info.name);
+    if let Err(e) = world.read_resource::<ScheduledEvents>().write(&events_path) {
+        log::error!("Could not save {}: {}", events_path.display(), e);
+    }
+
+    let players = world.read_storage::<Player>();
+    let inventories = world.read_storage::<Inventory>();
+    let player_path = WorldInfo::player_path(&info.name);
+    for (player, inventory) in (&players, &inventories).join() {
+        if let Err(e) = PlayerState::capture(player, inventory).write(&player_path) {
+            log::error!("Could not save {}: {}", player_path.display(), e);
+        }
+    }
+}
+
+/// The world as it was at one moment of play
+pub struct Snapshot {
+    pub name: String,
+    pub taken: SystemTime,
+    pub history: BlockHistory, // Shares unchanged chunks with the world and other snapshots
+    pub events: ScheduledEvents,
+    pub player: PlayerState,
+}
+
+/// Snapshots taken while playing, oldest first
+pub struct Snapshots {
+    pub list: Vec<Snapshot>,
+    taken_count: usize, // Used to name new snapshots
+    shown: bool,
+    selected: usize,
+    text: Entity,
+}
+
+impl Snapshots {
+    pub fn find(&self, name: &str) -> Option<&Snapshot> {
+        self.list.iter().find(|snapshot| snapshot.name == name)
+    }
+
+    /// Name for the next snapshot that is not given one
+    pub fn next_name(&mut self) -> String {
+        self.taken_count += 1;
+        format!("Snapshot {}", self.taken_count)
+    }
+
+    /// Snapshot the world under `name`, replacing an older snapshot with the same name
+    pub fn take(
+        &mut self,
+        name: String,
+        history: &BlockHistory,
+        events: &ScheduledEvents,
+        player: PlayerState,
+    ) {
+        self.list.retain(|snapshot| snapshot.name != name);
+        self.list.push(Snapshot {
+            name,
+            taken: SystemTime::now(),
+            history: history.clone(),
+            events: events.clone(),
+            player,
+        });
+    }
+}
+
+/// Put the world back the way it was in the snapshot
+pub fn restore_snapshot(
+    snapshot: &Snapshot,
+    history: &mut BlockHistory,
+    events: &mut ScheduledEvents,
+    player: &mut Player,
+    inventory: &mut Inventory,
+    undo: &mut UndoStack,
+) {
+    history.restore(&snapshot.history);
+    *events = snapshot.events.clone();
+    snapshot.player.apply(player, inventory);
+    undo.clear();
+}
+
+/// Save the snapshot as a new world called `name`, which is played independently from then on
+pub fn fork_world(info: &WorldInfo, snapshot: &Snapshot, name: &str) -> Result<WorldInfo, String> {
+    if WorldInfo::directory(name).exists() {
+        return Err(format!("A world called {} already exists", name));
+    }
+
+    let forked = WorldInfo {
+        name: name.to_string(),
+        ..info.clone()
+    };
+    forked.save();
+    snapshot.history.save(&WorldInfo::history_path(name))?;
+    snapshot
+        .events
+        .write(&WorldInfo::events_path(name))
+        .map_err(|e| e.to_string())?;
+    snapshot
+        .player
+        .write(&WorldInfo::player_path(name))
+        .map_err(|e| e.to_string())?;
+    Ok(forked)
+}
+
+/// Add the commands for taking, listing, restoring and forking snapshots
+pub fn register_snapshot_commands(commands: &mut CommandRegistry) {
+    commands.register("snapshot", "[name]", snapshot_command);
+    commands.register("snapshots", "", snapshots_command);
+    commands.register("restore", "<name>", restore_command);
+    commands.register("fork", "<snapshot> <world>", fork_command);
+}
+
+fn snapshot_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut snapshots = world.write_resource::<Snapshots>();
+    let name = if args.is_empty() {
+        snapshots.next_name()
+    } else {
+        args.join(" ")
+    };
+
+    let history = world.read_resource::<BlockHistory>();
+    let events = world.read_resource::<ScheduledEvents>();
+    let players = world.read_storage::<Player>();
+    let inventories = world.read_storage::<Inventory>();
+    let (player, inventory) = (&players, &inventories)
+        .join()
+        .next()
+        .ok_or("There is no player")?;
+    snapshots.take(
+        name.clone(),
+        &history,
+        &events,
+        PlayerState::capture(player, inventory),
+    );
+    snapshots.selected = snapshots.list.len() - 1;
+    Ok(format!("Took {}", name))
+}
+
+fn snapshots_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    if !args.is_empty() {
+        return Err("Too many arguments".to_string());
+    }
+    let snapshots = world.read_resource::<Snapshots>();
+    if snapshots.list.is_empty() {
+        return Ok("There are no snapshots".to_string());
+    }
+    Ok(snapshots
+        .list
+        .iter()
+        .map(|snapshot| format!("{}  {}", snapshot.name, clock_time(snapshot.taken)))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn restore_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    if args.is_empty() {
+        return Err("Missing name".to_string());
+    }
+    let name = args.join(" ");
+    let snapshots = world.read_resource::<Snapshots>();
+    let snapshot = snapshots
+        .find(&name)
+        .ok_or_else(|| format!("There is no snapshot called {}", name))?;
+
+    let mut players = world.write_storage::<Player>();
+    let mut inventories = world.write_storage::<Inventory>();
+    let (player, inventory) = (&mut players, &mut inventories)
+        .join()
+        .next()
+        .ok_or("There is no player")?;
+    restore_snapshot(
+        snapshot,
+        &mut world.write_resource::<BlockHistory>(),
+        &mut world.write_resource::<ScheduledEvents>(),
+        player,
+        inventory,
+        &mut world.write_resource::<UndoStack>(),
+    );
+    Ok(format!("Restored {}", name))
+}
+
+fn fork_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    // Snapshot names may have spaces, the world name is the last word
+    let (world_name, snapshot_name) = match args.split_last() {
+        Some((world_name, snapshot_name)) if !snapshot_name.is_empty() => {
+            (*world_name, snapshot_name.join(" "))
+        }
+        _ => return Err("Expected a snapshot and a world name".to_string()),
+    };
+    let snapshots = world.read_resource::<Snapshots>();
+    let snapshot = snapshots
+        .find(&snapshot_name)
+        .ok_or_else(|| format!("There is no snapshot called {}", snapshot_name))?;
+    fork_world(&world.read_resource::<WorldInfo>(), snapshot, world_name)?;
+    Ok(format!(
+        "Forked {} into the world {}",
+        snapshot_name, world_name
+    ))
+}
+
+/// Time of day in UTC, as hours, minutes and seconds
+fn clock_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+        % (24 * 60 * 60);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+pub fn initialize_snapshot_list(world: &mut World) {
+    let font: FontHandle = world.read_resource::<Loader>().load(
+        "Minecraft.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    );
+
+    let text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "snapshot list".to_string(),
+            Anchor::TopRight,
+            Anchor::TopRight,
+            -10.,
+            -10.,
+            5.,
+            420.,
+            400.,
+        ))
+        .with(UiText::new(
+            font,
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            20.,
+            LineMode::Wrap,
+            Anchor::TopLeft,
+        ))
+        .build();
+
+    world.insert(Snapshots {
+        list: Vec::new(),
+        taken_count: 0,
+        shown: false,
+        selected: 0,
+        text,
+    });
+}
+
+/// F6 takes a snapshot and F7 lists them. In the list, the arrow keys choose a snapshot,
+/// Enter restores it and F8 forks a new world from it.
+#[derive(SystemDesc)]
+#[system_desc(name(SnapshotSystemDesc))]
+pub struct SnapshotSystem {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<InputEvent<StringBindings>>,
+}
+
+impl SnapshotSystem {
+    pub fn new(event_reader: ReaderId<InputEvent<StringBindings>>) -> Self {
+        Self { event_reader }
+    }
+}
+
+impl<'s> System<'s> for SnapshotSystem {
+    type SystemData = (
+        WriteStorage<'s, Player>,
+        WriteStorage<'s, Inventory>,
+        WriteStorage<'s, UiText>,
+        Write<'s, BlockHistory>,
+        Write<'s, ScheduledEvents>,
+        WriteExpect<'s, UndoStack>,
+        WriteExpect<'s, Snapshots>,
+        ReadExpect<'s, WorldInfo>,
+        Read<'s, HideCursor>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut players,
+            mut inventories,
+            mut texts,
+            mut history,
+            mut scheduled,
+            mut undo,
+            mut snapshots,
+            info,
+            hide_cursor,
+            input_events,
+        ): Self::SystemData,
+    ) {
+        let snapshots = &mut *snapshots;
+
+        for event in input_events.read(&mut self.event_reader) {
+            let action = match event {
+                InputEvent::ActionPressed(action) if hide_cursor.hide => action.as_str(),
+                _ => continue,
+            };
+
+            match action {
+                "snapshot" => {
+                    let name = snapshots.next_name();
+                    for (player, inventory) in (&players, &inventories).join() {
+                        let state = PlayerState::capture(player, inventory);
+                        snapshots.take(name.clone(), &history, &scheduled, state);
+                    }
+                    snapshots.selected = snapshots.list.len().saturating_sub(1);
+                    log::info!("Took {}", name);
+                }
+                "snapshot_list" => snapshots.shown = !snapshots.shown,
+                "snapshot_previous" if snapshots.shown => {
+                    snapshots.selected = snapshots.selected.saturating_sub(1);
+                }
+                "snapshot_next" if snapshots.shown => {
+                    if snapshots.selected + 1 < snapshots.list.len() {
+                        snapshots.selected += 1;
+                    }
+                }
+                "snapshot_restore" if snapshots.shown => {
+                    if let Some(snapshot) = snapshots.list.get(snapshots.selected) {
+                        for (player, inventory) in (&mut players, &mut inventories).join() {
+                            restore_snapshot(
+                                snapshot,
+                                &mut history,
+                                &mut scheduled,
+                                player,
+                                inventory,
+                                &mut undo,
+                            );
+                        }
+                        log::info!("Restored {}", snapshot.name);
+                    }
+                }
+                "snapshot_fork" if snapshots.shown => {
+                    if let Some(snapshot) = snapshots.list.get(snapshots.selected) {
+                        let name = format!(
+                            "{}-{}",
+                            info.name,
+                            snapshot.name.to_lowercase().replace(' ', "-")
+                        );
+                        match fork_world(&info, snapshot, &name) {
+                            Ok(_) => log::info!("Forked {} into the world {}", snapshot.name, name),
+                            Err(e) => log::error!("Could not fork {}: {}", snapshot.name, e),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(text) = texts.get_mut(snapshots.text) {
+            text.text = if snapshots.shown {
+                let mut lines = vec!["Snapshots (F6 take, Enter restore, F8 fork)".to_string()];
+                for (i, snapshot) in snapshots.list.iter().enumerate() {
+                    let marker = if i == snapshots.selected { ">" } else { " " };
+                    lines.push(format!(
+                        "{} {}  {}",
+                        marker,
+                        snapshot.name,
+                        clock_time(snapshot.taken)
+                    ));
+                }
+                lines.join("\n")
+            } else {
+                "".to_string()
+            };
+        }
+    }
+}
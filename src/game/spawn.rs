@@ -0,0 +1,55 @@
+use amethyst::core::math::Vector3;
+
+use super::{BlockGrid, HEIGHT};
+
+/// How far from the origin to look for safe ground
+const SPAWN_SEARCH_RADIUS: i32 = 32;
+/// Space left between the feet and the ground, so the player never spawns inside a block
+const SPAWN_CLEARANCE: f32 = 0.1;
+
+/// Where the player appears when joining the world and after dying
+pub struct SpawnPoint {
+    pub position: Vector3<f32>,
+}
+
+impl SpawnPoint {
+    /// Standing on the column nearest the origin whose highest block is solid
+    pub fn find_safe(grid: &BlockGrid) -> Self {
+        for radius in 0..=SPAWN_SEARCH_RADIUS {
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    // Only the ring at this distance, closer columns were already checked
+                    if x.abs() != radius && z.abs() != radius {
+                        continue;
+                    }
+
+                    if let Some(top) = grid.highest_in_column(x, z) {
+                        if grid.is_solid((x, top, z)) {
+                            return Self {
+                                position: Vector3::new(
+                                    x as f32,
+                                    top as f32 + HEIGHT + SPAWN_CLEARANCE,
+                                    z as f32,
+                                ),
+                            };
+                        }
+                    }
+                }
+            }
+        }
+
+        // Nothing nearby is solid, stand on top of the origin column
+        let top = grid.highest_in_column(0, 0).unwrap_or(0);
+        Self {
+            position: Vector3::new(0., top as f32 + HEIGHT + SPAWN_CLEARANCE, 0.),
+        }
+    }
+}
+
+impl Default for SpawnPoint {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0., HEIGHT, 0.),
+        }
+    }
+}
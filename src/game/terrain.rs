@@ -0,0 +1,124 @@
+use noise::{NoiseFn, Perlin, Seedable};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{Block, BlockSurface};
+
+/// Generate the blocks of a world. The same seed always gives the same terrain.
+pub fn generate_terrain(seed: u32) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::with_capacity(1_000_000);
+    let perlin = Perlin::new().set_seed(seed);
+    let map_size = 64.;
+    let chunk_size = 128;
+    let max_height = 15.0;
+    // The noise stays within [-1, 1], so no column goes below -max_height. Filling every
+    // column down to there leaves no gap above the bedrock floor.
+    let min_height = -(max_height as isize);
+    let bedrock_y = min_height - 1;
+
+    let mut rng = StdRng::seed_from_u64(seed as u64);
+
+    // Random frequency in the range [7, 12)
+    let freq = rng.gen::<f64>() * 5.0 + 3.0;
+
+    // Iterate through x and z values of the map
+    for x in -(chunk_size / 2)..(chunk_size / 2) {
+        for z in -(chunk_size / 2)..(chunk_size / 2) {
+            let nx = (x as f32 / map_size - 1.0) as f64;
+            let nz = (z as f32 / map_size - 1.0) as f64;
+
+            // 3 octaves of Perlin noise
+            let y = (max_height
+                * (perlin.get([nx, nz])
+                    + 0.5 * perlin.get([freq * nx, freq * nz])
+                    + 0.25 * perlin.get([2.0 * freq * nx, 2.0 * freq * nz]))
+                / (1.0 + 0.5 + 0.25))
+                .round();
+
+            // Add top layer block
+            if y > 6.0 {
+                blocks.push(Block::new(x as f32, y as f32, z as f32, BlockSurface::Snow));
+            } else if y > -8.0 {
+                blocks.push(Block::new(
+                    x as f32,
+                    y as f32,
+                    z as f32,
+                    BlockSurface::Grass,
+                ));
+            } else if y > -10.0 {
+                blocks.push(Block::new(
+                    x as f32,
+                    y as f32,
+                    z as f32,
+                    BlockSurface::Gravel,
+                ));
+            } else {
+                // 50 % change of each type of stone
+                if rng.gen() {
+                    blocks.push(Block::new(
+                        x as f32,
+                        y as f32,
+                        z as f32,
+                        BlockSurface::StoneRough,
+                    ));
+                } else {
+                    blocks.push(Block::new(
+                        x as f32,
+                        y as f32,
+                        z as f32,
+                        BlockSurface::StoneSmooth,
+                    ));
+                }
+            }
+
+            // Add blocks below down to the minimum height.
+            // The type of block that is added depends on the height.
+            for i in min_height..y as isize {
+                if i > -5 {
+                    blocks.push(Block::new(
+                        x as f32,
+                        i as f32,
+                        z as f32,
+                        BlockSurface::Dirt,
+                    ));
+                } else if i > -8 {
+                    blocks.push(Block::new(
+                        x as f32,
+                        i as f32,
+                        z as f32,
+                        BlockSurface::Gravel,
+                    ));
+                } else {
+                    // 50 % change of each type of stone
+                    if rng.gen() {
+                        blocks.push(Block::new(
+                            x as f32,
+                            i as f32,
+                            z as f32,
+                            BlockSurface::StoneRough,
+                        ));
+                    } else {
+                        blocks.push(Block::new(
+                            x as f32,
+                            i as f32,
+                            z as f32,
+                            BlockSurface::StoneSmooth,
+                        ));
+                    }
+                }
+            }
+
+            // Unbreakable floor at the same height under every column
+            blocks.push(Block::new(
+                x as f32,
+                bedrock_y as f32,
+                z as f32,
+                BlockSurface::Bedrock,
+            ));
+        }
+    }
+
+    blocks.push(Block::new(0.0, 4.0, 0.0, BlockSurface::Dirt));
+    blocks.push(Block::new(0.0, 1.0, 0.0, BlockSurface::Dirt));
+
+    blocks
+}
@@ -1,16 +1,23 @@
 use amethyst::{
     assets::Loader,
     ecs::{Component, DenseVecStorage, Entity, ReadStorage, WriteStorage, Join},
+    input::{InputHandler, StringBindings},
     prelude::{Builder, WorldExt},
-    shred::{System, World, ReadExpect},
+    shred::{System, World, Read, ReadExpect},
     ui::{Anchor, FontHandle, LineMode, TtfFormat, UiText, UiTransform}, core::Transform,
 };
 
-use super::Player;
+use super::{
+    action_button, button_name, Inventory, MovementMode, Player, TimeAxis, WorldTime, MAX_HEALTH,
+};
 
 pub struct TextualUi {
     block_text: Entity, // Block name
-    coordinates_text: Entity
+    coordinates_text: Entity,
+    time_text: Entity,
+    mode_text: Entity, // Flying or spectating
+    health_text: Entity,
+    death_text: Entity // Only shown while dead
 }
 
 impl Component for TextualUi {
@@ -49,6 +56,50 @@ pub fn initialize_textual_ui(world: &mut World) {
         50.,
     );
 
+    let time_transform = UiTransform::new(
+        "world time".to_string(),
+        Anchor::TopLeft,
+        Anchor::TopLeft,
+        0.,
+        -50.,
+        0.,
+        270.,
+        50.,
+    );
+
+    let mode_transform = UiTransform::new(
+        "movement mode".to_string(),
+        Anchor::TopLeft,
+        Anchor::TopLeft,
+        0.,
+        -100.,
+        0.,
+        270.,
+        50.,
+    );
+
+    let health_transform = UiTransform::new(
+        "health".to_string(),
+        Anchor::BottomLeft,
+        Anchor::BottomLeft,
+        0.,
+        0.,
+        0.,
+        270.,
+        50.,
+    );
+
+    let death_transform = UiTransform::new(
+        "death".to_string(),
+        Anchor::Middle,
+        Anchor::Middle,
+        0.,
+        100.,
+        1.,
+        800.,
+        100.,
+    );
+
     // Set up UI components for texts
     let block_text = world
         .create_entity()
@@ -67,7 +118,7 @@ pub fn initialize_textual_ui(world: &mut World) {
         .create_entity()
         .with(coordinates_transform)
         .with(UiText::new(
-            font,
+            font.clone(),
             "None".to_string(),
             [1.0, 1.0, 1.0, 1.0],
             24.,
@@ -76,7 +127,66 @@ pub fn initialize_textual_ui(world: &mut World) {
         ))
         .build();
 
-    world.insert(TextualUi { block_text, coordinates_text });
+    let time_text = world
+        .create_entity()
+        .with(time_transform)
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            24.,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+
+    let mode_text = world
+        .create_entity()
+        .with(mode_transform)
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            24.,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+
+    let health_text = world
+        .create_entity()
+        .with(health_transform)
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            [1.0, 0.3, 0.3, 1.0],
+            24.,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+
+    let death_text = world
+        .create_entity()
+        .with(death_transform)
+        .with(UiText::new(
+            font,
+            "".to_string(),
+            [1.0, 0.2, 0.2, 1.0],
+            48.,
+            LineMode::Wrap,
+            Anchor::Middle,
+        ))
+        .build();
+
+    world.insert(TextualUi {
+        block_text,
+        coordinates_text,
+        time_text,
+        mode_text,
+        health_text,
+        death_text,
+    });
 }
 
 pub struct CurrentBlockUiSystem;
@@ -84,12 +194,16 @@ pub struct CurrentBlockUiSystem;
 impl<'s> System<'s> for CurrentBlockUiSystem {
     type SystemData = (
         ReadStorage<'s, Player>,
+        ReadStorage<'s, Inventory>,
         ReadStorage<'s, Transform>,
         WriteStorage<'s, UiText>,
-        ReadExpect<'s, TextualUi>
+        ReadExpect<'s, TextualUi>,
+        Read<'s, TimeAxis>,
+        Read<'s, WorldTime>,
+        Read<'s, InputHandler<StringBindings>>
     );
 
-    fn run(&mut self, (players, locals, mut text_uis, block_uis): Self::SystemData) {
+    fn run(&mut self, (players, inventories, locals, mut text_uis, block_uis, axis, world_time, input): Self::SystemData) {
         let (player, transform) = { // Get player entity
             let mut data = (None, None);
             for (local, player) in (&locals, &players).join() {
@@ -98,10 +212,13 @@ impl<'s> System<'s> for CurrentBlockUiSystem {
             data
         };
 
-        // Update block name text based on currently held block
+        // Update block name text based on the selected inventory slot
         if let Some(text) = text_uis.get_mut(block_uis.block_text) {
-            if let Some(block_name) = &player.unwrap().current_block {
-                text.text = block_name.1.to_string();
+            for inventory in (&inventories).join() {
+                text.text = match inventory.selected_stack() {
+                    Some(stack) => format!("{} x{}", stack.surface.to_string(), stack.count),
+                    None => "None".to_string(),
+                };
             }
         }
 
@@ -112,5 +229,39 @@ impl<'s> System<'s> for CurrentBlockUiSystem {
                 text.text = format!("{:.3} {:.3} {:.3}", transl.x, transl.y, transl.z);
             }
         }
+
+        // Update world time
+        if let Some(text) = text_uis.get_mut(block_uis.time_text) {
+            text.text = match world_time.offset {
+                0 => format!("Time: {} ({})", world_time.now, axis),
+                offset => format!("Time: {} ({}, {} back)", world_time.now, axis, -offset),
+            };
+        }
+
+        // Update health and the death screen
+        if let Some(player) = player {
+            if let Some(text) = text_uis.get_mut(block_uis.health_text) {
+                text.text = format!("Health: {:.0}/{:.0}", player.health.ceil(), MAX_HEALTH);
+            }
+            if let Some(text) = text_uis.get_mut(block_uis.death_text) {
+                text.text = if player.is_dead() {
+                    // The respawn key can be changed in the settings
+                    let key = button_name(action_button(&input.bindings, "respawn"));
+                    format!("You died! Press {} to respawn", key)
+                } else {
+                    "".to_string()
+                };
+            }
+        }
+
+        // Show movement mode unless walking
+        if let Some(text) = text_uis.get_mut(block_uis.mode_text) {
+            if let Some(player) = player {
+                text.text = match player.mode {
+                    MovementMode::Walking => "".to_string(),
+                    mode => mode.to_string(),
+                };
+            }
+        }
     }
 }
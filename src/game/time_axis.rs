@@ -0,0 +1,173 @@
+use std::fmt;
+
+use amethyst::{
+    assets::Handle,
+    controls::HideCursor,
+    core::{math::Vector3, Transform},
+    ecs::{Entities, Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    input::{InputHandler, StringBindings},
+    renderer::{Material, Mesh},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{Block, BlockAssets, BlockGrid, BlockHistory, Player};
+
+/// The spatial axis that acts as the time dimension of a world
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TimeAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl Default for TimeAxis {
+    fn default() -> Self {
+        Self::X
+    }
+}
+
+impl fmt::Display for TimeAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::X => "X",
+            Self::Y => "Y",
+            Self::Z => "Z",
+        })
+    }
+}
+
+impl TimeAxis {
+    /// The axis after this one, going back to X after Z
+    pub fn next(&self) -> Self {
+        match self {
+            Self::X => Self::Y,
+            Self::Y => Self::Z,
+            Self::Z => Self::X,
+        }
+    }
+
+    /// World time at a position, the coordinate along the axis rounded to a whole cell
+    pub fn time_at(&self, position: &Vector3<f32>) -> i32 {
+        let coordinate = match self {
+            Self::X => position.x,
+            Self::Y => position.y,
+            Self::Z => position.z,
+        };
+        coordinate.round() as i32
+    }
+}
+
+/// Time of the world the player currently sees and edits
+#[derive(Default)]
+pub struct WorldTime {
+    pub now: i32,
+    pub offset: i32, // How far behind the player's own time, never above 0
+}
+
+/// Follows the player along the time axis and swaps the blocks around them
+/// for the state of the world at the new time.
+///
+/// The time can be rewound behind the player's own time to edit the past,
+/// and the edits then carry forward to the player's time.
+#[derive(Default)]
+pub struct TimeSyncSystem {
+    shown_time: Option<i32>, // Time the blocks in the grid belong to
+    back_held: bool,
+    forward_held: bool,
+}
+
+impl<'s> System<'s> for TimeSyncSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Player>,
+        WriteStorage<'s, Block>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Handle<Mesh>>,
+        WriteStorage<'s, Handle<Material>>,
+        Read<'s, TimeAxis>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, HideCursor>,
+        Write<'s, WorldTime>,
+        Write<'s, BlockHistory>,
+        Write<'s, BlockGrid>,
+        ReadExpect<'s, BlockAssets>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            players,
+            mut blocks,
+            mut locals,
+            mut meshes,
+            mut materials,
+            axis,
+            input,
+            hide_cursor,
+            mut world_time,
+            mut history,
+            mut grid,
+            assets,
+        ): Self::SystemData,
+    ) {
+        // Step the time back or towards the present once per key press
+        let back = hide_cursor.hide && input.action_is_down("time_back").unwrap_or(false);
+        let forward = hide_cursor.hide && input.action_is_down("time_forward").unwrap_or(false);
+        if back && !self.back_held {
+            world_time.offset -= 1;
+        }
+        if forward && !self.forward_held {
+            world_time.offset = (world_time.offset + 1).min(0);
+        }
+        self.back_held = back;
+        self.forward_held = forward;
+
+        for player in (&players).join() {
+            world_time.now = axis.time_at(&player.position) + world_time.offset;
+        }
+
+        // Only cells with a change between the shown and the current time, or a new change
+        // in their history, can differ from what is in the grid
+        let mut cells = history.take_dirty();
+        if let Some(shown) = self.shown_time {
+            if shown != world_time.now {
+                // Going back or forward in time, cells anywhere in the world may change
+                let (t0, t1) = (shown.min(world_time.now), shown.max(world_time.now));
+                let min = (i32::MIN, i32::MIN, i32::MIN);
+                let max = (i32::MAX, i32::MAX, i32::MAX);
+                let changes = history.changes_in_region(min, max, t0, t1);
+                cells.extend(changes.iter().map(|change| change.cell));
+            }
+        }
+        self.shown_time = Some(world_time.now);
+
+        for cell in cells {
+            let surface = match history.state_at(cell, world_time.now) {
+                Some(surface) => surface,
+                None => continue,
+            };
+            if grid.surface(cell) == surface {
+                continue;
+            }
+
+            if let Some(entity) = grid.remove(cell) {
+                entities.delete(entity).unwrap();
+            }
+            if let Some(surface) = surface {
+                let (x, y, z) = (cell.0 as f32, cell.1 as f32, cell.2 as f32);
+                let mut transform = Transform::default();
+                transform.append_translation_xyz(x, y, z);
+
+                let entity = entities
+                    .build_entity()
+                    .with(Block::new(x, y, z, surface), &mut blocks)
+                    .with(transform, &mut locals)
+                    .with(assets.mesh.clone(), &mut meshes)
+                    .with(assets.material(surface), &mut materials)
+                    .build();
+                grid.insert(cell, entity, surface);
+            }
+        }
+    }
+}
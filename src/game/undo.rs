@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use amethyst::{
+    controls::HideCursor,
+    ecs::{Join, Read, ReadStorage, System, Write, WriteExpect, WriteStorage},
+    input::{InputHandler, StringBindings},
+};
+
+use super::{BlockHistory, BlockSurface, Cell, GameMode, Insertion, Inventory, Player, TimedBlock};
+
+/// One block changed by the player
+#[derive(Clone, Copy, Debug)]
+pub struct CellEdit {
+    pub time: i32,
+    pub cell: Cell,
+    pub before: Option<BlockSurface>,
+    pub after: Option<BlockSurface>,
+    pub dropped: Option<TimedBlock>, // Later event the edit made redundant, put back by undo
+}
+
+impl CellEdit {
+    /// The edit that takes this one back
+    fn inverse(&self) -> Self {
+        Self {
+            before: self.after,
+            after: self.before,
+            ..*self
+        }
+    }
+}
+
+/// Edits undone and redone together, e.g. every block of a multi-block operation
+pub type EditGroup = Vec<CellEdit>;
+
+/// The local player's latest edits, oldest first
+pub struct UndoStack {
+    done: VecDeque<EditGroup>,
+    undone: Vec<EditGroup>,
+    depth: usize,
+}
+
+impl UndoStack {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            depth,
+        }
+    }
+
+    /// Forget every edit, e.g. when the world is restored to a snapshot
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    /// Remember a new group of edits, which makes anything undone impossible to redo
+    pub fn push(&mut self, group: EditGroup) {
+        if group.is_empty() || self.depth == 0 {
+            return;
+        }
+        self.undone.clear();
+        self.remember(group);
+    }
+
+    fn remember(&mut self, group: EditGroup) {
+        self.done.push_back(group);
+        while self.done.len() > self.depth {
+            self.done.pop_front();
+        }
+    }
+}
+
+/// Make `edit` in the history if its cell is still in the state the edit expects,
+/// returning the edit as it was made. Cells changed since by something else are left alone.
+fn apply_edit(
+    edit: &CellEdit,
+    undoing: bool,
+    history: &mut BlockHistory,
+    mut inventory: Option<&mut Inventory>,
+) -> Option<CellEdit> {
+    let current = history
+        .state_at(edit.cell, edit.time)
+        .unwrap_or(edit.before);
+    if current != edit.before {
+        return None;
+    }
+
+    // In survival a block put back has to come from the inventory
+    if let (Some(inventory), Some(surface)) = (inventory.as_ref(), edit.after) {
+        if inventory.find(surface).is_none() {
+            return None;
+        }
+    }
+
+    // An undo removes the original change so the history stays as if it never happened
+    let made = if undoing {
+        history
+            .revert(edit.time, edit.cell, edit.before, edit.dropped)
+            .then_some(*edit)
+    } else {
+        match history.record(edit.time, edit.cell, edit.before, edit.after) {
+            Insertion::Recorded { dropped } => Some(CellEdit { dropped, ..*edit }),
+            Insertion::Skipped => None,
+        }
+    };
+
+    if let (Some(_), Some(inventory)) = (made, inventory.as_mut()) {
+        if let Some(surface) = edit.after {
+            inventory.remove_one(surface);
+        }
+        if let Some(surface) = edit.before {
+            inventory.add(surface, 1);
+        }
+    }
+    made
+}
+
+/// Undoes and redoes block edits with Ctrl+Z and Ctrl+Y
+#[derive(Default)]
+pub struct UndoSystem {
+    undo_held: bool,
+    redo_held: bool,
+}
+
+impl<'s> System<'s> for UndoSystem {
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        WriteStorage<'s, Inventory>,
+        Write<'s, BlockHistory>,
+        WriteExpect<'s, UndoStack>,
+        Read<'s, GameMode>,
+        Read<'s, HideCursor>,
+        Read<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(
+        &mut self,
+        (players, mut inventories, mut history, mut stack, game_mode, hide_cursor, input): Self::SystemData,
+    ) {
+        let undo = input.action_is_down("undo").unwrap_or(false);
+        let redo = input.action_is_down("redo").unwrap_or(false);
+        let (undo_pressed, redo_pressed) = (undo && !self.undo_held, redo && !self.redo_held);
+        self.undo_held = undo;
+        self.redo_held = redo;
+
+        let alive = (&players).join().any(|player| !player.is_dead());
+        if !alive || !hide_cursor.hide || !(undo_pressed || redo_pressed) {
+            return;
+        }
+
+        let popped = if undo_pressed {
+            stack.done.pop_back()
+        } else {
+            stack.undone.pop()
+        };
+        let group = match popped {
+            Some(group) => group,
+            None => return,
+        };
+
+        // Undo takes back the edits last to first, redo makes them again in order
+        let edits: EditGroup = if undo_pressed {
+            group.iter().rev().map(CellEdit::inverse).collect()
+        } else {
+            group
+        };
+
+        let mut applied = EditGroup::new();
+        for edit in edits.iter() {
+            let inventory = match *game_mode {
+                GameMode::Survival => (&mut inventories).join().next(),
+                GameMode::Creative => None,
+            };
+            if let Some(made) = apply_edit(edit, undo_pressed, &mut history, inventory) {
+                applied.push(made);
+            } else {
+                log::info!("Skipped the block at {:?}, it was changed since", edit.cell);
+            }
+        }
+
+        if applied.is_empty() {
+            return;
+        }
+        if undo_pressed {
+            stack
+                .undone
+                .push(applied.iter().rev().map(CellEdit::inverse).collect());
+        } else {
+            stack.remember(applied);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fs, path::PathBuf};
+
+use amethyst::{config::Config, utils::application_root_dir};
+use serde::{Deserialize, Serialize};
+
+use super::{GameMode, TimeAxis};
+
+/// Name of the world played when no other world is chosen
+pub const DEFAULT_WORLD_NAME: &str = "world";
+
+/// Everything needed to recreate a world, saved in `saves/<name>/world.ron`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WorldInfo {
+    pub name: String,
+    pub seed: u32,
+    pub spawn: Option<[f32; 3]>, // Computed from the terrain the first time the world is played
+    #[serde(default)]
+    pub game_mode: GameMode,
+    #[serde(default)]
+    pub time_axis: TimeAxis, // Chosen when the world is created and never changed
+}
+
+impl WorldInfo {
+    /// A new world with a random seed
+    pub fn new(name: &str, game_mode: GameMode, time_axis: TimeAxis) -> Self {
+        Self {
+            name: name.to_string(),
+            seed: rand::random(),
+            spawn: None,
+            game_mode,
+            time_axis,
+        }
+    }
+
+    /// Load the world with the given name, or create it if it has never been saved
+    pub fn load_or_create(name: &str) -> Self {
+        let path = Self::file_path(name);
+        if path.exists() {
+            match Self::load(&path) {
+                Ok(info) => return info,
+                Err(e) => log::error!("Could not load {}: {}", path.display(), e),
+            }
+        }
+        Self::new(name, GameMode::default(), TimeAxis::default())
+    }
+
+    /// Name for a new world that is not taken by a saved one, like `world-2`
+    pub fn unused_name() -> String {
+        let mut name = DEFAULT_WORLD_NAME.to_string();
+        let mut number = 1;
+        while Self::directory(&name).exists() {
+            number += 1;
+            name = format!("{}-{}", DEFAULT_WORLD_NAME, number);
+        }
+        name
+    }
+
+    /// Names of all saved worlds, in alphabetical order
+    pub fn saved_names() -> Vec<String> {
+        let entries = match fs::read_dir(saves_directory()) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| Self::file_path(name).exists())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn save(&self) {
+        let path = Self::file_path(&self.name);
+        let result = fs::create_dir_all(path.parent().unwrap())
+            .map_err(|e| e.to_string())
+            .and_then(|_| self.write(&path).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::error!("Could not save {}: {}", path.display(), e);
+        }
+    }
+
+    /// Directory all data of the world is saved in
+    pub fn directory(name: &str) -> PathBuf {
+        saves_directory().join(name)
+    }
+
+    fn file_path(name: &str) -> PathBuf {
+        Self::directory(name).join("world.ron")
+    }
+
+    /// File the block history of the world is saved in
+    pub fn history_path(name: &str) -> PathBuf {
+        Self::directory(name).join("history.ron")
+    }
+
+    /// File the scheduled events of the world are saved in
+    pub fn events_path(name: &str) -> PathBuf {
+        Self::directory(name).join("events.ron")
+    }
+
+    /// File the player of the world is saved in
+    pub fn player_path(name: &str) -> PathBuf {
+        Self::directory(name).join("player.ron")
+    }
+}
+
+/// Directory containing one subdirectory per world
+pub fn saves_directory() -> PathBuf {
+    application_root_dir()
+        .map(|root| root.join("saves"))
+        .unwrap_or_else(|_| PathBuf::from("saves"))
+}
@@ -2,14 +2,14 @@ extern crate amethyst;
 mod game;
 
 use amethyst::{
-    controls::{CursorHideSystemDesc, FreeRotationSystemDesc, MouseFocusUpdateSystemDesc},
+    controls::{CursorHideSystemDesc, MouseFocusUpdateSystemDesc},
     core::transform::TransformBundle,
     input::{InputBundle, StringBindings},
     prelude::*,
     renderer::{bundle::RenderingBundle, types::DefaultBackend, RenderFlat3D, RenderToWindow},
     start_logger,
     ui::{RenderUi, UiBundle},
-    utils::application_root_dir,
+    utils::{application_root_dir, fps_counter::FpsCounterBundle},
 };
 
 fn main() -> amethyst::Result<()> {
@@ -18,44 +18,73 @@ fn main() -> amethyst::Result<()> {
     let root = application_root_dir()?;
     let disp = root.join("config/display.ron");
     let key_bindings_path = root.join("config/input.ron");
+    let physics_config_path = root.join("config/physics.ron");
     let assets = root.join("assets");
     let input_bundle =
         InputBundle::<StringBindings>::new().with_bindings_from_file(key_bindings_path)?;
 
-    let sensitivity_x = 1.0;
-    let sensitivity_y = 1.0;
-    let game_data = GameDataBuilder::default()
-        .with_system_desc(
-            FreeRotationSystemDesc::new(sensitivity_x, sensitivity_y),
-            "free_rotation",
-            &[],
-        )
-        .with_system_desc(
-            MouseFocusUpdateSystemDesc::default(),
-            "mouse_focus",
-            &["free_rotation"],
-        )
-        .with_system_desc(
+    // Input, UI and rendering run in every state, the rest only while a world is played
+    let game_data = game::CustomGameDataBuilder::default()
+        .with_core_system_desc(MouseFocusUpdateSystemDesc::default(), "mouse_focus", &[])
+        .with_core_system_desc(
             CursorHideSystemDesc::default(),
             "cursor_hide",
             &["mouse_focus"],
         )
-        .with_bundle(
+        .with_core_bundle(
             RenderingBundle::<DefaultBackend>::new()
                 .with_plugin(
                     RenderToWindow::from_config_path(disp)?.with_clear([0.2, 0.5, 1.0, 1.0]),
                 )
                 .with_plugin(RenderFlat3D::default())
                 .with_plugin(RenderUi::default()),
-        )?
-        .with_bundle(TransformBundle::new())?
-        .with_bundle(input_bundle)?
-        .with_bundle(game::movement::MovementBundle)?
-        .with_bundle(UiBundle::<StringBindings>::new())?
-        .with(game::CurrentBlockUiSystem, "BlockUiSystem", &[])
-        .with_system_desc(game::MouseRaycastSystemDesc, "mouse_raycast", &[]);
+        )
+        .with_core_bundle(TransformBundle::new())
+        .with_core_bundle(input_bundle)
+        .with_core_bundle(UiBundle::<StringBindings>::new())
+        .with_core_bundle(FpsCounterBundle)
+        .with_running_bundle(game::movement::MovementBundle {
+            physics_config_path,
+        })
+        .with_running(game::CurrentBlockUiSystem, "BlockUiSystem", &[])
+        .with_running(game::RespawnSystem, "respawn", &[])
+        .with_running(game::VoidRecoverySystem, "void_recovery", &["movement"])
+        .with_running(
+            game::TimeSyncSystem::default(),
+            "time_sync",
+            &["void_recovery"],
+        )
+        .with_running_system_desc(
+            game::MouseRaycastSystemDesc,
+            "mouse_raycast",
+            &["time_sync"],
+        )
+        .with_running(game::UndoSystem::default(), "undo", &["mouse_raycast"])
+        .with_running_system_desc(game::SnapshotSystemDesc, "snapshot", &["undo"])
+        .with_running(
+            game::ScheduledEventSystem,
+            "scheduled_events",
+            &["snapshot"],
+        )
+        .with_running(
+            game::DebugOverlaySystem::default(),
+            "debug_overlay",
+            &["movement"],
+        )
+        .with_running_system_desc(game::HotbarSystemDesc, "hotbar", &["mouse_raycast"])
+        .with_running_system_desc(
+            game::InventoryScreenSystemDesc,
+            "inventory_screen",
+            &["mouse_raycast", "hotbar"],
+        )
+        .with_running_system_desc(
+            game::CreativePaletteSystemDesc,
+            "creative_palette",
+            &["inventory_screen"],
+        )
+        .with_running_system_desc(game::ConsoleSystemDesc, "console", &["creative_palette"]);
 
-    let mut game = Application::new(assets, game::InGame, game_data)?;
+    let mut game = Application::new(assets, game::MainMenu::default(), game_data)?;
     game.run();
 
     Ok(())